@@ -0,0 +1,77 @@
+//! Per-candidate costs of the search loop: key generation, address encoding and matching.
+//! Run with `cargo bench`; compare against a saved baseline with `--save-baseline`/`--baseline`.
+
+use bip39::{Language, Mnemonic};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{thread_rng, RngCore};
+use solana_sdk::signature::{Keypair, SeedDerivable, Signer};
+use solana_vanity_seed::{
+    mnemonic_seed, mnemonic_seeds, BothMatcher, KeySource, Matcher, MnemonicKeySource, PrefixMatcher,
+    RawKeySource, RegexMatcher, SuffixMatcher, LANES,
+};
+
+fn keypairs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keypair");
+    group.bench_function("Keypair::new", |b| b.iter(Keypair::new));
+    group.bench_function("RawKeySource", |b| {
+        let mut rng = thread_rng();
+        b.iter(|| RawKeySource.generate(&mut rng))
+    });
+    group.finish();
+}
+
+fn mnemonics(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mnemonic");
+    let mut entropy = [0u8; 16];
+    thread_rng().fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy).unwrap();
+    let seed = mnemonic.to_seed("");
+    group.bench_function("from_entropy", |b| {
+        b.iter(|| Mnemonic::from_entropy_in(Language::English, black_box(&entropy)).unwrap())
+    });
+    group.bench_function("to_seed (PBKDF2)", |b| b.iter(|| black_box(&mnemonic).to_seed("")));
+    group.bench_function("mnemonic_seed", |b| b.iter(|| mnemonic_seed(black_box(&mnemonic))));
+    let batch = vec![mnemonic.clone(); LANES];
+    group.bench_function(format!("mnemonic_seed (x{}, one at a time)", LANES), |b| {
+        b.iter(|| black_box(&batch).iter().map(mnemonic_seed).collect::<Vec<_>>())
+    });
+    group.bench_function(format!("mnemonic_seeds (x{})", LANES), |b| b.iter(|| mnemonic_seeds(black_box(&batch))));
+    group.bench_function("keypair from seed", |b| b.iter(|| Keypair::from_seed(black_box(&seed[..32])).unwrap()));
+    for words in [12, 24] {
+        let source = MnemonicKeySource::new(words);
+        group.bench_function(format!("MnemonicKeySource ({} words)", words), |b| {
+            let mut rng = thread_rng();
+            b.iter(|| source.generate(&mut rng))
+        });
+    }
+    group.finish();
+}
+
+fn base58(c: &mut Criterion) {
+    let pubkey = Keypair::new().pubkey();
+    c.bench_function("base58/pubkey to_string", |b| b.iter(|| black_box(&pubkey).to_string()));
+}
+
+fn matchers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match");
+    let addresses: Vec<String> = (0..64).map(|_| Keypair::new().pubkey().to_string()).collect();
+    let cases: Vec<(&str, Box<dyn Matcher>)> = vec![
+        ("prefix", Box::new(PrefixMatcher::new("Sun").unwrap())),
+        ("suffix", Box::new(SuffixMatcher::new("xyz").unwrap())),
+        ("both", Box::new(BothMatcher::new("Sun", "xyz").unwrap())),
+        ("regex", Box::new(RegexMatcher::new("^Sun|xyz$").unwrap())),
+    ];
+    for (name, matcher) in &cases {
+        group.bench_function(*name, |b| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 1) % addresses.len();
+                matcher.is_match(black_box(&addresses[i]))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, keypairs, mnemonics, base58, matchers);
+criterion_main!(benches);
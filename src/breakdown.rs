@@ -0,0 +1,136 @@
+use crate::error::VanityError;
+use crate::matcher::Matcher;
+use crate::search::{GenerationMode, VanitySearch};
+use crate::seed::{mnemonic_seeds, LANES};
+use bip39::{Language, Mnemonic};
+use rand::{thread_rng, RngCore};
+use solana_sdk::signature::{Keypair, SeedDerivable, Signer};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Average time one stage of candidate generation takes
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub secs_per_candidate: f64,
+}
+
+/// Accumulates time per stage in pipeline order
+struct Stages(Vec<(&'static str, Duration)>);
+
+impl Stages {
+    fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let out = f();
+        let elapsed = start.elapsed();
+        match self.0.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => self.0.push((stage, elapsed)),
+        }
+        out
+    }
+}
+
+/// Time each stage of producing and testing a candidate on the calling thread, mirroring what the
+/// built-in key sources and the search loop do. The last entry, "search loop overhead", is the
+/// difference between a real single-threaded search and the sum of the stages.
+pub fn candidate_breakdown(
+    generation: GenerationMode,
+    words: usize,
+    matcher: Arc<dyn Matcher>,
+    samples: u32,
+) -> Result<Vec<StageTiming>, VanityError> {
+    let mut rng = thread_rng();
+    let mut stages = Stages(Vec::new());
+    // Mnemonic seeds are derived `LANES` at a time, as the search loop does
+    let group = if generation == GenerationMode::Mnemonic { LANES as u32 } else { 1 };
+    let groups = samples.div_ceil(group);
+    for _ in 0..groups {
+        let keypairs = match generation {
+            GenerationMode::Raw | GenerationMode::Token => {
+                let seed = stages.time("random seed", || {
+                    let mut seed = [0u8; 32];
+                    rng.fill_bytes(&mut seed);
+                    seed
+                });
+                vec![stages.time("ed25519 keypair", || Keypair::from_seed(&seed).unwrap())]
+            }
+            GenerationMode::Mnemonic => {
+                let entropy = stages.time("random entropy", || {
+                    let mut entropy = vec![0u8; group as usize * if words == 12 { 16 } else { 32 }];
+                    rng.fill_bytes(&mut entropy);
+                    entropy
+                });
+                let mnemonics = stages.time("bip39 mnemonic", || {
+                    entropy
+                        .chunks(entropy.len() / group as usize)
+                        .map(|e| Mnemonic::from_entropy_in(Language::English, e).unwrap())
+                        .collect::<Vec<_>>()
+                });
+                let seeds = stages.time("bip39 seed (PBKDF2)", || mnemonic_seeds(&mnemonics));
+                stages.time("ed25519 keypair", || {
+                    seeds.iter().map(|seed| Keypair::from_seed(&seed[..32]).unwrap()).collect()
+                })
+            }
+        };
+        for keypair in keypairs {
+            let address = stages.time("base58 encode", || keypair.pubkey().to_string());
+            black_box(stages.time("match", || matcher.is_match(&address)));
+        }
+    }
+    let candidates = (groups * group) as f64;
+    let mut timings: Vec<StageTiming> = stages
+        .0
+        .into_iter()
+        .map(|(stage, total)| StageTiming { stage, secs_per_candidate: total.as_secs_f64() / candidates })
+        .collect();
+    let stage_sum: f64 = timings.iter().map(|t| t.secs_per_candidate).sum();
+    let loop_secs = search_loop_secs(generation, words, matcher, samples)?;
+    timings.push(StageTiming { stage: "search loop overhead", secs_per_candidate: (loop_secs - stage_sum).max(0.0) });
+    Ok(timings)
+}
+
+/// Seconds per candidate for a real single-threaded search capped at `samples` attempts
+fn search_loop_secs(
+    generation: GenerationMode,
+    words: usize,
+    matcher: Arc<dyn Matcher>,
+    samples: u32,
+) -> Result<f64, VanityError> {
+    let mut measured = None;
+    let result = VanitySearch::new()
+        .generation(generation)
+        .words(words)
+        .matcher(NeverHit(matcher))
+        .threads(1)
+        .max_attempts(samples.into())
+        .run(|event| {
+            if let crate::SearchEvent::Stopped { progress, .. } = event {
+                measured = Some(progress.elapsed_secs / progress.session_attempts.max(1) as f64);
+            }
+        });
+    match (result, measured) {
+        (Err(VanityError::BudgetExhausted), Some(secs)) => Ok(secs),
+        (Err(e), _) => Err(e),
+        (Ok(_), _) => unreachable!("NeverHit matches nothing"),
+    }
+}
+
+/// Does the inner matcher's work but never reports a hit, so the timed search runs to its cap
+struct NeverHit(Arc<dyn Matcher>);
+
+impl Matcher for NeverHit {
+    fn is_match(&self, address: &str) -> bool {
+        black_box(self.0.is_match(address));
+        false
+    }
+
+    fn probability(&self) -> f64 {
+        self.0.probability()
+    }
+
+    fn describe(&self) -> String {
+        self.0.describe()
+    }
+}
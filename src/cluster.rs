@@ -0,0 +1,572 @@
+//! Distributed search: a coordinator serves one search over TCP and workers on other machines
+//! run it with their own threads, reporting throughput and submitting the match.
+//!
+//! The protocol is one JSON object per line. A worker sends `hello`, receives `job` (or
+//! `rejected`), then sends `progress` every few seconds and `hit` if it finds the match. The
+//! coordinator sends `stop` to every worker when the search ends. Traffic is not encrypted, so
+//! keep it on a trusted network or tunnel it (e.g. `ssh -L`).
+
+use crate::error::VanityError;
+use crate::matcher::Matcher;
+use crate::search::{deliver, probability_after, GenerationMode, Hit, Progress, SearchEvent, VanitySearch};
+use crate::seed::mnemonic_seed;
+use crate::state::SearchConfig;
+use bip39::{Language, Mnemonic};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, SeedDerivable, Signer};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Protocol revision; coordinator and workers must agree
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How often workers report progress
+const REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A worker that sends nothing for this long is dropped
+const WORKER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time allowed for the hello/job exchange and for the coordinator to acknowledge a hit
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the coordinator checks for new workers, the stop flag and the limits
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest protocol line accepted
+const MAX_LINE: u64 = 64 * 1024;
+
+/// Messages from a worker
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToCoordinator {
+    Hello { version: u32, name: Option<String>, threads: usize, token: Option<String> },
+    /// Attempts so far in this worker's search and its current rate
+    Progress { attempts: u64, rate: f64 },
+    Hit { attempts: u64, private_key: String, mnemonic: Option<String> },
+}
+
+/// Messages from the coordinator
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ToWorker {
+    Job { config: SearchConfig },
+    Rejected { reason: String },
+    Stop { reason: String },
+}
+
+/// How a worker's part in a coordinated search ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerOutcome {
+    /// This worker found the match and handed it to the coordinator
+    Found { public_address: String },
+    /// The coordinator ended the search (another worker's match, its budget or an interrupt)
+    Stopped { reason: String },
+}
+
+fn send<T: Serialize>(stream: &mut TcpStream, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message).map_err(io::Error::other)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+/// Read the next message; `None` at end of stream
+fn receive<T: DeserializeOwned>(reader: &mut BufReader<TcpStream>) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    let n = reader.by_ref().take(MAX_LINE).read_line(&mut line)?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "protocol line too long"));
+    }
+    serde_json::from_str(&line).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// What a connection thread tells the coordinator loop
+enum Incoming {
+    Joined { name: Option<String>, threads: usize, writer: TcpStream },
+    Progress { attempts: u64, rate: f64 },
+    Hit { attempts: u64, private_key: String, mnemonic: Option<String> },
+    Left { reason: String },
+}
+
+/// Coordinator-side view of one connection
+struct Peer {
+    name: String,
+    /// Set once the worker has been sent the job
+    writer: Option<TcpStream>,
+    attempts: u64,
+    rate: f64,
+    /// Dropped for sending an invalid match (already reported)
+    dropped: bool,
+}
+
+/// The coordinator's connections; a worker is removed once its connection ends, keeping its count
+#[derive(Default)]
+struct Peers {
+    connected: HashMap<usize, Peer>,
+    next_id: usize,
+    /// Attempts reported by workers that have left
+    departed_attempts: u64,
+}
+
+impl Peers {
+    fn attempts(&self) -> u64 {
+        self.departed_attempts + self.connected.values().map(|p| p.attempts).sum::<u64>()
+    }
+
+    fn rate(&self) -> f64 {
+        self.connected.values().filter(|p| p.writer.is_some()).map(|p| p.rate).sum()
+    }
+}
+
+/// Handshake with one worker, then forward its messages until it disconnects
+fn serve_worker(
+    id: usize,
+    stream: TcpStream,
+    config: &SearchConfig,
+    token: Option<&str>,
+    tx: &Sender<(usize, Incoming)>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let (name, threads) = match receive(&mut reader)? {
+        Some(ToCoordinator::Hello { version, name, threads, token: offered }) => {
+            let refusal = if version != PROTOCOL_VERSION {
+                Some(format!("protocol version {} is not supported (expected {})", version, PROTOCOL_VERSION))
+            } else if token.is_some() && offered.as_deref() != token {
+                Some("wrong cluster token".to_string())
+            } else {
+                None
+            };
+            if let Some(reason) = refusal {
+                let _ = send(&mut writer, &ToWorker::Rejected { reason: reason.clone() });
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+            }
+            (name, threads)
+        }
+        Some(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected hello")),
+        None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "closed before hello")),
+    };
+    send(&mut writer, &ToWorker::Job { config: config.clone() })?;
+    reader.get_ref().set_read_timeout(Some(WORKER_TIMEOUT))?;
+    if tx.send((id, Incoming::Joined { name, threads, writer })).is_err() {
+        return Ok(());
+    }
+    loop {
+        let incoming = match receive(&mut reader)? {
+            None => return Ok(()),
+            Some(ToCoordinator::Progress { attempts, rate }) => Incoming::Progress { attempts, rate },
+            Some(ToCoordinator::Hit { attempts, private_key, mnemonic }) => {
+                Incoming::Hit { attempts, private_key, mnemonic }
+            }
+            Some(ToCoordinator::Hello { .. }) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected hello"))
+            }
+        };
+        if tx.send((id, incoming)).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Rebuild a reported match and check that it really is one
+fn verify_hit(
+    matcher: &dyn Matcher,
+    config: &SearchConfig,
+    private_key: &str,
+    mnemonic: Option<String>,
+) -> Result<(Keypair, Option<String>), String> {
+    let bytes = bs58::decode(private_key).into_vec().map_err(|_| "private key is not Base58".to_string())?;
+    if bytes.len() != 64 {
+        return Err("private key has the wrong length".to_string());
+    }
+    // Derive from the secret half rather than trusting the public half
+    let keypair = Keypair::from_seed(&bytes[..32]).map_err(|e| e.to_string())?;
+    if !matcher.is_match(&keypair.pubkey().to_string()) {
+        return Err("address does not match the pattern".to_string());
+    }
+    match (config.generation == "mnemonic", &mnemonic) {
+        (true, Some(phrase)) => {
+            let parsed = Mnemonic::parse_in(Language::English, phrase).map_err(|e| e.to_string())?;
+            if parsed.word_count() != config.words {
+                return Err(format!("mnemonic has {} words, not {}", parsed.word_count(), config.words));
+            }
+            let derived = Keypair::from_seed(&mnemonic_seed(&parsed)[..32]).map_err(|e| e.to_string())?;
+            if derived.pubkey() != keypair.pubkey() {
+                return Err("mnemonic does not derive this key".to_string());
+            }
+        }
+        (true, None) => return Err("mnemonic missing".to_string()),
+        (false, Some(_)) => return Err("unexpected mnemonic".to_string()),
+        (false, None) => {}
+    }
+    Ok((keypair, mnemonic))
+}
+
+impl VanitySearch {
+    /// Serve this search to workers connecting on `listener` instead of running it locally.
+    /// Workers that present a different `token` are turned away. Progress is the sum over all
+    /// workers; the first verified match stops every worker and is handed to the sinks.
+    /// `max_attempts` is checked against the workers' reports, so a few more keys may be tried.
+    /// Custom matchers, key sources and state files cannot be distributed.
+    pub fn coordinate<F>(self, listener: TcpListener, token: Option<String>, mut on_event: F) -> Result<Hit, VanityError>
+    where
+        F: FnMut(SearchEvent<'_>),
+    {
+        if self.matcher.is_some() || self.source.is_some() {
+            return Err(VanityError::InvalidInput("custom matchers and key sources cannot be sent to workers".to_string()));
+        }
+        if self.checkpoint.is_some() {
+            return Err(VanityError::InvalidInput("state files are not supported when coordinating workers".to_string()));
+        }
+        let matcher = self.build_matcher()?;
+        let config = SearchConfig { threads: 0, ..self.config()? };
+        listener.set_nonblocking(true).map_err(|e| VanityError::io("cannot listen for workers", e))?;
+        let stop = self.stop.clone().unwrap_or_default();
+        let start = Instant::now();
+        let deadline = self.limits.max_time.and_then(|d| start.checked_add(d));
+        let avg_tries = 1.0 / matcher.probability();
+        let (tx, rx) = mpsc::channel();
+        let token: Option<Arc<str>> = token.map(Arc::from);
+        let mut peers = Peers::default();
+        let snapshot = |peers: &Peers, update: u64| {
+            let (attempts, rate) = (peers.attempts(), peers.rate());
+            let remaining = avg_tries - attempts as f64;
+            Progress {
+                update,
+                attempts,
+                session_attempts: attempts,
+                elapsed_secs: start.elapsed().as_secs_f64(),
+                rate,
+                probability: probability_after(attempts, avg_tries),
+                eta_secs: if remaining > 0.0 && rate > 0.0 { Some(remaining / rate) } else { None },
+            }
+        };
+        on_event(SearchEvent::Started { config: &config, resumed_attempts: 0, resumed_elapsed_secs: 0.0 });
+        let mut updates = 0;
+        let mut last_report = Instant::now();
+        let outcome = loop {
+            accept_workers(&listener, &mut peers, &config, &token, &tx);
+            if let Some(hit) = handle_incoming(&rx, &mut peers, matcher.as_ref(), &config, &mut on_event) {
+                let (keypair, mnemonic) = hit;
+                break Ok(Hit { keypair, mnemonic, progress: snapshot(&peers, updates) });
+            }
+            let attempts = peers.attempts();
+            if stop.load(Ordering::Relaxed) {
+                break Err((VanityError::Interrupted, "interrupted"));
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) || self.limits.max_attempts.is_some_and(|m| attempts >= m) {
+                break Err((VanityError::BudgetExhausted, "budget exhausted"));
+            }
+            if last_report.elapsed() >= self.progress_interval {
+                updates += 1;
+                on_event(SearchEvent::Progress { progress: &snapshot(&peers, updates) });
+                last_report = Instant::now();
+            }
+        };
+        let reason = match &outcome {
+            Ok(_) => "match found",
+            Err((_, reason)) => reason,
+        };
+        for writer in peers.connected.values_mut().filter_map(|p| p.writer.as_mut()) {
+            let _ = send(writer, &ToWorker::Stop { reason: reason.to_string() });
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+        match outcome {
+            Ok(hit) => {
+                deliver(self.sinks, &hit, &config, &mut on_event);
+                Ok(hit)
+            }
+            Err((err, reason)) => {
+                on_event(SearchEvent::Stopped { reason, progress: &snapshot(&peers, updates) });
+                Err(err)
+            }
+        }
+    }
+
+    /// Join the coordinator at `addr` and search its job with this builder's threads, placement,
+    /// limits and stop flag (the job replaces any patterns and generation mode set here).
+    /// The match, if this worker finds it, goes to the coordinator; local sinks also receive it.
+    pub fn join_cluster<F>(
+        self,
+        addr: impl ToSocketAddrs,
+        name: Option<String>,
+        token: Option<String>,
+        mut on_event: F,
+    ) -> Result<WorkerOutcome, VanityError>
+    where
+        F: FnMut(SearchEvent<'_>),
+    {
+        let lost = |e: io::Error| VanityError::io("lost connection to the coordinator", e);
+        let stream = TcpStream::connect(addr).map_err(|e| VanityError::io("cannot connect to the coordinator", e))?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(lost)?;
+        let mut writer = stream.try_clone().map_err(lost)?;
+        let mut reader = BufReader::new(stream);
+        let hello = ToCoordinator::Hello { version: PROTOCOL_VERSION, name, threads: self.thread_count(), token };
+        send(&mut writer, &hello).map_err(lost)?;
+        let config = match receive(&mut reader).map_err(lost)? {
+            Some(ToWorker::Job { config }) => config,
+            Some(ToWorker::Rejected { reason }) => {
+                return Err(VanityError::InvalidInput(format!("coordinator rejected this worker: {}", reason)))
+            }
+            Some(ToWorker::Stop { reason }) => return Ok(WorkerOutcome::Stopped { reason }),
+            None => return Err(lost(io::ErrorKind::UnexpectedEof.into())),
+        };
+        let generation = GenerationMode::from_name(&config.generation).ok_or_else(|| {
+            VanityError::InvalidInput(format!("coordinator sent unknown generation mode {:?}", config.generation))
+        })?;
+        let stop = self.stop.clone().unwrap_or_default();
+        // Progress goes to the coordinator every REPORT_INTERVAL but to `on_event` as configured
+        let local_interval = self.progress_interval;
+        let mut last_local = Instant::now();
+        let mut search = self;
+        search.prefix = config.prefix;
+        search.suffix = config.suffix;
+        search.regex = config.regex;
+        search.matcher = None;
+        search.source = None;
+        let mut search = search
+            .generation(generation)
+            .progress_interval(REPORT_INTERVAL)
+            .stop_flag(Arc::clone(&stop));
+        if generation == GenerationMode::Mnemonic {
+            search = search.words(config.words);
+        }
+        // The coordinator's stop (or a dropped connection) raises the local stop flag
+        reader.get_ref().set_read_timeout(None).map_err(lost)?;
+        let (stop_tx, stop_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let ended = loop {
+                match receive::<ToWorker>(&mut reader) {
+                    Ok(Some(ToWorker::Stop { reason })) => break Ok(reason),
+                    Ok(Some(_)) => continue,
+                    Ok(None) => break Err(io::ErrorKind::UnexpectedEof.into()),
+                    Err(e) => break Err(e),
+                }
+            };
+            let _ = stop_tx.send(ended);
+            stop.store(true, Ordering::Relaxed);
+        });
+        let result = search.run(|event| {
+            match &event {
+                SearchEvent::Progress { progress } | SearchEvent::Stopped { progress, .. } => {
+                    let report = ToCoordinator::Progress { attempts: progress.session_attempts, rate: progress.rate };
+                    let _ = send(&mut writer, &report);
+                }
+                _ => {}
+            }
+            if let SearchEvent::Progress { .. } = event {
+                if last_local.elapsed() < local_interval {
+                    return;
+                }
+                last_local = Instant::now();
+            }
+            on_event(event)
+        });
+        let coordinator_stop = |stop_rx: &Receiver<io::Result<String>>| match stop_rx.try_recv() {
+            Ok(Ok(reason)) => Some(Ok(WorkerOutcome::Stopped { reason })),
+            Ok(Err(e)) => Some(Err(lost(e))),
+            Err(_) => None,
+        };
+        match result {
+            Ok(hit) => {
+                let public_address = hit.keypair.pubkey().to_string();
+                let report = ToCoordinator::Hit {
+                    attempts: hit.progress.session_attempts,
+                    private_key: bs58::encode(&hit.keypair.to_bytes()).into_string(),
+                    mnemonic: hit.mnemonic,
+                };
+                send(&mut writer, &report).map_err(|e| VanityError::io("cannot send the match to the coordinator", e))?;
+                // Wait for the coordinator's stop so the match isn't lost to an early close
+                let _ = stop_rx.recv_timeout(HANDSHAKE_TIMEOUT);
+                Ok(WorkerOutcome::Found { public_address })
+            }
+            Err(VanityError::Interrupted) => coordinator_stop(&stop_rx).unwrap_or(Err(VanityError::Interrupted)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Take every pending connection and start its handshake on a thread of its own
+fn accept_workers(
+    listener: &TcpListener,
+    peers: &mut Peers,
+    config: &SearchConfig,
+    token: &Option<Arc<str>>,
+    tx: &Sender<(usize, Incoming)>,
+) {
+    // Accept errors (e.g. a client resetting mid-handshake) only affect that client
+    while let Ok((stream, addr)) = listener.accept() {
+        let id = peers.next_id;
+        peers.next_id += 1;
+        peers.connected.insert(id, Peer { name: addr.to_string(), writer: None, attempts: 0, rate: 0.0, dropped: false });
+        let (config, token, tx) = (config.clone(), token.clone(), tx.clone());
+        thread::spawn(move || {
+            let reason = match stream.set_nonblocking(false).and_then(|_| serve_worker(id, stream, &config, token.as_deref(), &tx)) {
+                Ok(()) => "disconnected".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let _ = tx.send((id, Incoming::Left { reason }));
+        });
+    }
+}
+
+/// Apply connection messages for up to one poll interval; returns a verified match
+fn handle_incoming(
+    rx: &Receiver<(usize, Incoming)>,
+    peers: &mut Peers,
+    matcher: &dyn Matcher,
+    config: &SearchConfig,
+    on_event: &mut dyn FnMut(SearchEvent<'_>),
+) -> Option<(Keypair, Option<String>)> {
+    let until = Instant::now() + POLL_INTERVAL;
+    loop {
+        let (id, incoming) = match rx.recv_timeout(until.saturating_duration_since(Instant::now())) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+        };
+        // `Left` is the last message of a connection, so every other one finds its peer
+        let Some(peer) = peers.connected.get_mut(&id) else { continue };
+        match incoming {
+            Incoming::Joined { name, threads, writer } => {
+                if let Some(name) = name {
+                    peer.name = format!("{} ({})", name, peer.name);
+                }
+                peer.writer = Some(writer);
+                on_event(SearchEvent::WorkerJoined { worker: &peer.name, threads });
+            }
+            // Counts only grow, so a late report can't undo a newer one
+            Incoming::Progress { attempts, rate } => {
+                peer.attempts = peer.attempts.max(attempts);
+                peer.rate = rate;
+            }
+            Incoming::Hit { attempts, private_key, mnemonic } => {
+                peer.attempts = peer.attempts.max(attempts);
+                match verify_hit(matcher, config, &private_key, mnemonic) {
+                    Ok(found) => return Some(found),
+                    Err(why) => {
+                        let reason = format!("sent an invalid match: {}", why);
+                        on_event(SearchEvent::WorkerLeft { worker: &peer.name, reason: &reason });
+                        peer.dropped = true;
+                        peer.rate = 0.0;
+                        if let Some(writer) = peer.writer.take() {
+                            let _ = writer.shutdown(Shutdown::Both);
+                        }
+                    }
+                }
+            }
+            Incoming::Left { reason } => {
+                if !peer.dropped {
+                    on_event(SearchEvent::WorkerLeft { worker: &peer.name, reason: &reason });
+                }
+                if let Some(peer) = peers.connected.remove(&id) {
+                    peers.departed_attempts += peer.attempts;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::SuffixMatcher;
+
+    fn serve(search: VanitySearch, token: Option<&str>) -> (String, thread::JoinHandle<Result<Hit, VanityError>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let token = token.map(str::to_string);
+        (addr, thread::spawn(move || search.coordinate(listener, token, |_| {})))
+    }
+
+    #[test]
+    fn worker_match_reaches_the_coordinator() {
+        // A budget too far out for an Instant must not panic the coordinator
+        let search = VanitySearch::new().generation(GenerationMode::Raw).suffix("a").max_time(Duration::MAX);
+        let (addr, coordinator) = serve(search, Some("t"));
+        let outcome = VanitySearch::new().threads(1).join_cluster(addr.as_str(), None, Some("t".into()), |_| {}).unwrap();
+        let hit = coordinator.join().unwrap().unwrap();
+        assert!(hit.keypair.pubkey().to_string().ends_with('a'));
+        assert_eq!(outcome, WorkerOutcome::Found { public_address: hit.keypair.pubkey().to_string() });
+    }
+
+    #[test]
+    fn wrong_token_is_rejected() {
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let search = VanitySearch::new().generation(GenerationMode::Raw).suffix("a").stop_flag(Arc::clone(&stop));
+        let (addr, coordinator) = serve(search, Some("right"));
+        let joined = VanitySearch::new().threads(1).join_cluster(addr.as_str(), None, Some("wrong".into()), |_| {});
+        assert!(matches!(joined, Err(VanityError::InvalidInput(_))));
+        stop.store(true, Ordering::Relaxed);
+        assert!(matches!(coordinator.join().unwrap(), Err(VanityError::Interrupted)));
+    }
+
+    #[test]
+    fn departed_workers_are_removed_but_counted() {
+        let mut peers = Peers::default();
+        for id in 0..2 {
+            peers.next_id += 1;
+            peers.connected.insert(id, Peer { name: id.to_string(), writer: None, attempts: 0, rate: 0.0, dropped: false });
+        }
+        let (tx, rx) = mpsc::channel();
+        tx.send((0, Incoming::Progress { attempts: 500, rate: 10.0 })).unwrap();
+        tx.send((1, Incoming::Progress { attempts: 200, rate: 5.0 })).unwrap();
+        tx.send((0, Incoming::Left { reason: "disconnected".into() })).unwrap();
+        let matcher = SuffixMatcher::new("a").unwrap();
+        let config = SearchConfig {
+            prefix: None,
+            suffix: Some("a".into()),
+            regex: None,
+            matcher: None,
+            generation: "raw".into(),
+            words: 12,
+            threads: 0,
+        };
+        let mut left = Vec::new();
+        let hit = handle_incoming(&rx, &mut peers, &matcher, &config, &mut |event| {
+            if let SearchEvent::WorkerLeft { worker, .. } = event {
+                left.push(worker.to_string());
+            }
+        });
+        assert!(hit.is_none());
+        assert_eq!(left, ["0"]);
+        assert_eq!(peers.connected.keys().collect::<Vec<_>>(), [&1]);
+        assert_eq!(peers.attempts(), 700);
+    }
+
+    #[test]
+    fn forged_hits_are_refused() {
+        let matcher = SuffixMatcher::new("a").unwrap();
+        let config = |generation: &str| SearchConfig {
+            prefix: None,
+            suffix: Some("a".into()),
+            regex: None,
+            matcher: None,
+            generation: generation.into(),
+            words: 12,
+            threads: 0,
+        };
+        let keypair = (0u8..).map(|i| Keypair::from_seed(&[i; 32]).unwrap()).find(|k| k.pubkey().to_string().ends_with('a')).unwrap();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        assert!(verify_hit(&matcher, &config("raw"), &secret, None).is_ok());
+        // A non-matching secret paired with the matching public key
+        let other = (0u8..).map(|i| Keypair::from_seed(&[i; 32]).unwrap()).find(|k| !k.pubkey().to_string().ends_with('a')).unwrap();
+        let mut forged = other.to_bytes();
+        forged[32..].copy_from_slice(&keypair.pubkey().to_bytes());
+        assert!(verify_hit(&matcher, &config("raw"), &bs58::encode(forged).into_string(), None).is_err());
+        assert!(verify_hit(&matcher, &config("raw"), "not base58!", None).is_err());
+        // A mnemonic that doesn't derive the key
+        let phrase = Mnemonic::from_entropy(&[0u8; 16]).unwrap().to_string();
+        assert!(verify_hit(&matcher, &config("mnemonic"), &secret, Some(phrase)).is_err());
+        assert!(verify_hit(&matcher, &config("mnemonic"), &secret, None).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Running a search in a local Docker or Podman container: the image the cloud CPU executor uses, with
+//! resource limits, and the result written to a host directory mounted into the container.
+
+use crate::error::VanityError;
+use crate::executor::{CommandRunner, Executor, JobHandle, JobSpec, JobStatus, ResourceLimits};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tag given to the image built from the repo's Dockerfile when no other image is named
+pub const DEFAULT_IMAGE: &str = "solana-vanity-seed:local";
+
+/// Where the results directory is mounted inside the container
+const RESULTS_MOUNT: &str = "/results";
+
+/// File in the results directory the container's search appends its match to
+pub const RESULT_FILE: &str = "result.jsonl";
+
+/// Label on every container we start, so finished ones can be pruned together
+const LABEL: &str = "solana-vanity-seed";
+
+/// Container engine CLI to drive (both take the same commands)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    pub fn program(&self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+
+    /// Run the search as the invoking user, so the result file it creates is readable on the host
+    fn user_args(&self) -> Vec<String> {
+        match self {
+            // Rootless podman maps the container's root to this user unless told to keep our id
+            ContainerEngine::Podman => vec!["--userns=keep-id".to_string()],
+            #[cfg(unix)]
+            ContainerEngine::Docker => {
+                // SAFETY: getuid/getgid cannot fail
+                let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+                vec!["--user".to_string(), format!("{}:{}", uid, gid)]
+            }
+            #[cfg(not(unix))]
+            ContainerEngine::Docker => Vec::new(),
+        }
+    }
+}
+
+/// Runs the search in a container whose entrypoint is `solana-vanity-seed` (the repo's Dockerfile),
+/// optionally building that image first
+pub struct ContainerExecutor {
+    engine: ContainerEngine,
+    image: String,
+    /// Build context (a directory with a Dockerfile) to build `image` from before each run
+    build: Option<PathBuf>,
+    limits: ResourceLimits,
+    /// Parent of the per-job directories mounted into the containers
+    results_dir: PathBuf,
+    cancelled: HashSet<String>,
+    runner: Box<dyn CommandRunner>,
+}
+
+impl ContainerExecutor {
+    /// Run `image` with `engine`, collecting results under `./vanity-results`
+    pub fn new(engine: ContainerEngine, image: impl Into<String>, runner: impl CommandRunner + 'static) -> Self {
+        ContainerExecutor {
+            engine,
+            image: image.into(),
+            build: None,
+            limits: ResourceLimits::default(),
+            results_dir: PathBuf::from("vanity-results"),
+            cancelled: HashSet::new(),
+            runner: Box::new(runner),
+        }
+    }
+
+    /// Build the image from the Dockerfile in `context` before running it
+    pub fn build(mut self, context: impl Into<PathBuf>) -> Self {
+        self.build = Some(context.into());
+        self
+    }
+
+    /// Limit the container's CPUs and memory, and give it GPUs
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Collect each job's result in a subdirectory of `dir`
+    pub fn results_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.results_dir = dir.into();
+        self
+    }
+
+    fn command(&self, args: &[&str]) -> Vec<String> {
+        let mut argv = vec![self.engine.program().to_string()];
+        argv.extend(args.iter().map(|a| a.to_string()));
+        argv
+    }
+
+    /// Host directory mounted into the container `name`
+    fn job_dir(&self, name: &str) -> PathBuf {
+        let dir = self.results_dir.join(name);
+        std::path::absolute(&dir).unwrap_or(dir)
+    }
+
+    /// Commands that start the container `name`
+    fn commands(&self, job: &JobSpec, name: &str) -> Vec<Vec<String>> {
+        let mut plan = Vec::new();
+        if let Some(context) = &self.build {
+            let context = context.display().to_string();
+            let dockerfile = Path::new(&context).join("Dockerfile").display().to_string();
+            plan.push(self.command(&["build", "--quiet", "--tag", &self.image, "--file", &dockerfile, &context]));
+        }
+        let mount = format!("{}:{}", self.job_dir(name).display(), RESULTS_MOUNT);
+        let mut run = self.command(&["run", "--detach", "--name", name, "--label", LABEL, "--volume", &mount]);
+        run.extend(self.engine.user_args());
+        let flags = [
+            ("--cpus", self.limits.cpus.map(|n| n.to_string())),
+            ("--memory", self.limits.memory_mib.map(|n| format!("{}m", n))),
+            ("--gpus", self.limits.gpus.map(|n| n.to_string())),
+        ];
+        for (flag, value) in flags {
+            if let Some(value) = value {
+                run.extend([flag.to_string(), value]);
+            }
+        }
+        run.push(self.image.clone());
+        run.extend(job.args.iter().cloned());
+        run.extend(["--sink".to_string(), format!("jsonl:{}/{}", RESULTS_MOUNT, RESULT_FILE)]);
+        plan.push(run);
+        plan
+    }
+
+    fn state(&mut self, job: &JobHandle) -> Result<Value, VanityError> {
+        let command = format!("{} inspect", self.engine.program());
+        let stdout = self.runner.run(&self.command(&["inspect", "--format", "{{json .State}}", &job.id]))?;
+        serde_json::from_str(stdout.trim())
+            .map_err(|e| VanityError::io(format!("unexpected output from {}", command), io::Error::other(e)))
+    }
+}
+
+/// Container name for a new run of `job`
+fn container_name(job: &JobSpec) -> String {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    format!("{}-{}", job.name, stamp)
+}
+
+impl Executor for ContainerExecutor {
+    fn name(&self) -> String {
+        self.engine.program().to_string()
+    }
+
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>> {
+        self.commands(job, &container_name(job))
+    }
+
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError> {
+        let name = container_name(job);
+        let dir = self.job_dir(&name);
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            // The result file holds the secret key
+            builder.mode(0o700);
+        }
+        builder.create(&dir).map_err(|e| VanityError::io(format!("cannot create {}", dir.display()), e))?;
+        for argv in self.commands(job, &name) {
+            self.runner.run(&argv)?;
+        }
+        Ok(JobHandle { executor: self.name(), id: name })
+    }
+
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError> {
+        let state = self.state(job)?;
+        let code = state["ExitCode"].as_i64().unwrap_or_default();
+        Ok(match state["Status"].as_str().unwrap_or_default() {
+            "created" | "configured" => JobStatus::Pending,
+            "running" | "paused" | "restarting" | "stopping" => JobStatus::Running,
+            _ if self.cancelled.contains(&job.id) => JobStatus::Cancelled,
+            _ if state["OOMKilled"] == true => JobStatus::Failed { reason: "out of memory".to_string() },
+            "exited" | "stopped" if code == 0 => JobStatus::Succeeded,
+            "exited" | "stopped" => JobStatus::Failed { reason: format!("exit code {}", code) },
+            other => JobStatus::Failed { reason: format!("unknown container status {:?}", other) },
+        })
+    }
+
+    /// The container's standard output (its diagnostics stay in `docker logs`)
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError> {
+        self.runner.run(&self.command(&["logs", &job.id]))
+    }
+
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError> {
+        self.cancelled.insert(job.id.clone());
+        // SIGTERM, then SIGKILL after the engine's grace period
+        self.runner.run(&self.command(&["stop", &job.id])).map(drop)
+    }
+
+    fn result_file(&self, job: &JobHandle) -> Option<PathBuf> {
+        Some(self.job_dir(&job.id).join(RESULT_FILE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::testing::ScriptedRunner;
+
+    #[test]
+    fn container_round_trip() {
+        let runner = ScriptedRunner::replying(&[
+            "sha256:abc",
+            "f00d",
+            r#"{"Status":"running","ExitCode":0}"#,
+            r#"{"Status":"exited","ExitCode":3,"OOMKilled":false}"#,
+            r#"{"Status":"exited","ExitCode":137,"OOMKilled":true}"#,
+            "Public Address: X\n",
+            "",
+            r#"{"Status":"exited","ExitCode":0}"#,
+        ]);
+        let results = std::env::temp_dir().join(format!("vanity-container-test-{}", std::process::id()));
+        let limits = ResourceLimits { cpus: Some(2), memory_mib: Some(512), gpus: None };
+        let mut executor = ContainerExecutor::new(ContainerEngine::Podman, "vanity:test", runner.clone())
+            .build(".")
+            .limits(limits)
+            .results_dir(&results);
+        let job = JobSpec { name: "vanity".into(), args: ["--suffix", "a b"].map(String::from).to_vec() };
+        let handle = executor.submit(&job).unwrap();
+        let dir = results.join(&handle.id);
+        assert!(dir.is_dir());
+        assert_eq!(executor.result_file(&handle), Some(dir.join(RESULT_FILE)));
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Running);
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Failed { reason: "exit code 3".into() });
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Failed { reason: "out of memory".into() });
+        assert_eq!(executor.logs(&handle).unwrap(), "Public Address: X\n");
+        executor.cancel(&handle).unwrap();
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Cancelled);
+        let calls = runner.calls();
+        assert_eq!(calls[0][..3], ["podman", "build", "--quiet"]);
+        let run = &calls[1];
+        let mount = format!("{}:/results", dir.display());
+        assert!(run.windows(2).any(|w| w == ["--volume", mount.as_str()]), "{:?}", run);
+        assert!(run.windows(2).any(|w| w == ["--memory", "512m"]), "{:?}", run);
+        assert_eq!(run[run.len() - 5..], ["vanity:test", "--suffix", "a b", "--sink", "jsonl:/results/result.jsonl"]);
+        assert_eq!(calls[6], ["podman", "stop", &handle.id]);
+        fs::remove_dir_all(&results).unwrap();
+    }
+}
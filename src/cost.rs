@@ -0,0 +1,47 @@
+/// Throughput and price of one place a search can run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    pub keys_per_sec: f64,
+    pub usd_per_hour: f64,
+}
+
+/// Time and money until a match has been found with some probability
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub probability: f64,
+    pub secs: f64,
+    pub usd: f64,
+}
+
+/// Attempts after which a match has been seen with `probability` (inverse of `probability_after`)
+pub fn attempts_for_probability(probability: f64, avg_tries: f64) -> f64 {
+    -(1.0 - probability).ln() * avg_tries
+}
+
+impl CostModel {
+    /// Expected time and cost to reach `probability` for a pattern needing `avg_tries` attempts on average
+    pub fn estimate(&self, avg_tries: f64, probability: f64) -> Estimate {
+        let secs = attempts_for_probability(probability, avg_tries) / self.keys_per_sec;
+        Estimate { probability, secs, usd: secs / 3_600.0 * self.usd_per_hour }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probability_after;
+
+    #[test]
+    fn estimates_invert_the_success_probability() {
+        let avg_tries = 1_000_000.0;
+        for probability in [0.5, 0.9, 0.99] {
+            let attempts = attempts_for_probability(probability, avg_tries);
+            assert!((probability_after(attempts.round() as u64, avg_tries) - probability).abs() < 1e-6);
+        }
+        let model = CostModel { keys_per_sec: 1_000.0, usd_per_hour: 3.6 };
+        let estimate = model.estimate(avg_tries, 0.5);
+        // ln 2 × 1000 s
+        assert!((estimate.secs - 693.147).abs() < 0.01, "{:?}", estimate);
+        assert!((estimate.usd - 0.693).abs() < 0.001, "{:?}", estimate);
+    }
+}
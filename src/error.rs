@@ -0,0 +1,49 @@
+use std::io;
+
+/// Errors that end a search, each mapped to a distinct process exit code
+#[derive(Debug)]
+pub enum VanityError {
+    /// Bad patterns, arguments or state files (exit code 2)
+    InvalidInput(String),
+    /// The time or attempt budget ran out before a match was found (exit code 3)
+    BudgetExhausted,
+    /// Stopped by the caller's stop flag (SIGINT/SIGTERM in the CLI) before a match was found (exit code 130)
+    Interrupted,
+    /// Reading or writing a file failed (exit code 4)
+    Io { context: String, source: io::Error },
+}
+
+impl VanityError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            VanityError::InvalidInput(_) => 2,
+            VanityError::BudgetExhausted => 3,
+            VanityError::Io { .. } => 4,
+            VanityError::Interrupted => 130,
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        VanityError::Io { context: context.into(), source }
+    }
+}
+
+impl std::fmt::Display for VanityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VanityError::InvalidInput(msg) => write!(f, "{}", msg),
+            VanityError::BudgetExhausted => write!(f, "search budget exhausted before a match was found"),
+            VanityError::Interrupted => write!(f, "search interrupted before a match was found"),
+            VanityError::Io { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for VanityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VanityError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,695 @@
+//! Running a search somewhere other than this process: a local background process or a cloud batch
+//! service. Cloud executors drive the provider's CLI through a `CommandRunner`, which tests replace
+//! with a scripted one.
+
+use crate::error::VanityError;
+use crate::sink::create_secret_file;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// A search to run elsewhere: a job name and the `solana-vanity-seed` arguments (without the program)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSpec {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// A submitted job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobHandle {
+    /// Name of the executor that ran it
+    pub executor: String,
+    /// Executor-specific id (process id, AWS Batch job id, Cloud Run execution name)
+    pub id: String,
+}
+
+/// Where a job is in its lifecycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Queued or starting
+    Pending,
+    Running,
+    Succeeded,
+    Failed { reason: String },
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has stopped for good
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Pending | JobStatus::Running)
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobStatus::Pending => write!(f, "pending"),
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Succeeded => write!(f, "succeeded"),
+            JobStatus::Failed { reason } => write!(f, "failed: {}", reason),
+            JobStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// Search progress a running job has reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JobProgress {
+    /// Attempts so far, summed over the job's machines
+    pub attempts: u64,
+    /// Keys per second, summed over the job's machines
+    pub rate: f64,
+    /// Machines that have reported progress
+    pub reporting: usize,
+}
+
+/// Resources to request for each job (unset values keep the executor's defaults)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    pub cpus: Option<u32>,
+    pub memory_mib: Option<u64>,
+    pub gpus: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == ResourceLimits::default()
+    }
+}
+
+/// Launches and tracks search jobs
+pub trait Executor: Send {
+    /// Short name used in messages and job handles
+    fn name(&self) -> String;
+
+    /// Commands `submit` runs for `job`, in order (shown by `--dry-run`)
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>>;
+
+    /// Start the job
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError>;
+
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError>;
+
+    /// Output the job has written so far
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError>;
+
+    /// Ask the job to stop; it may take a moment to reach `JobStatus::Cancelled`
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError>;
+
+    /// Latest progress, for executors that can see the search's own reports
+    fn progress(&mut self, _job: &JobHandle) -> Result<Option<JobProgress>, VanityError> {
+        Ok(None)
+    }
+
+    /// File on this machine the job stores its result in, for executors that collect one
+    fn result_file(&self, _job: &JobHandle) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Runs external commands for the cloud executors
+pub trait CommandRunner: Send {
+    /// Run `argv` to completion and return its stdout; a non-zero exit is an error
+    fn run(&mut self, argv: &[String]) -> Result<String, VanityError>;
+}
+
+/// Runs commands as child processes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, argv: &[String]) -> Result<String, VanityError> {
+        let output = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| VanityError::io(format!("cannot run {}", argv[0]), e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(VanityError::io(
+                format!("{} failed ({})", shell_join(&argv[..argv.len().min(3)]), output.status),
+                io::Error::other(stderr.trim().to_string()),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Quote `arg` for a POSIX shell if it needs it
+pub fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+=:,./@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+/// Render `argv` as one POSIX shell command line
+pub fn shell_join(argv: &[String]) -> String {
+    argv.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ")
+}
+
+fn strings<const N: usize>(parts: [&str; N]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+/// Parse a CLI's JSON output
+fn parse_json(command: &str, stdout: &str) -> Result<Value, VanityError> {
+    serde_json::from_str(stdout)
+        .map_err(|e| VanityError::io(format!("unexpected output from {}", command), io::Error::other(e)))
+}
+
+/// Fail with a clear message when a CLI's JSON lacks a field
+fn missing(command: &str, field: &str) -> VanityError {
+    VanityError::io(format!("unexpected output from {}", command), io::Error::other(format!("no {}", field)))
+}
+
+/// Runs the search as a background process of this machine, with output in a log file
+pub struct LocalProcessExecutor {
+    program: PathBuf,
+    log_dir: PathBuf,
+    jobs: HashMap<String, LocalJob>,
+}
+
+struct LocalJob {
+    child: Child,
+    log: PathBuf,
+    cancelled: bool,
+}
+
+impl LocalProcessExecutor {
+    /// Run `program` (normally this binary) and write each job's output to a file in `log_dir`
+    pub fn new(program: impl Into<PathBuf>, log_dir: impl Into<PathBuf>) -> Self {
+        LocalProcessExecutor { program: program.into(), log_dir: log_dir.into(), jobs: HashMap::new() }
+    }
+
+    fn job(&mut self, job: &JobHandle) -> Result<&mut LocalJob, VanityError> {
+        self.jobs
+            .get_mut(&job.id)
+            .ok_or_else(|| VanityError::InvalidInput(format!("no local job {}", job.id)))
+    }
+}
+
+impl Executor for LocalProcessExecutor {
+    fn name(&self) -> String {
+        "process".to_string()
+    }
+
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>> {
+        let mut argv = vec![self.program.display().to_string()];
+        argv.extend(job.args.iter().cloned());
+        vec![argv]
+    }
+
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError> {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+        let log = self.log_dir.join(format!("{}-{}-{}.log", job.name, stamp, self.jobs.len()));
+        // The log receives the found key, so it gets the same protection as other secret files
+        let err = |e| VanityError::io(format!("cannot create {}", log.display()), e);
+        let stdout = create_secret_file(&log).map_err(err)?;
+        let stderr = stdout.try_clone().map_err(err)?;
+        let child = Command::new(&self.program)
+            .args(&job.args)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()
+            .map_err(|e| VanityError::io(format!("cannot start {}", self.program.display()), e))?;
+        let id = child.id().to_string();
+        self.jobs.insert(id.clone(), LocalJob { child, log, cancelled: false });
+        Ok(JobHandle { executor: self.name(), id })
+    }
+
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError> {
+        let local = self.job(job)?;
+        let exit = local.child.try_wait().map_err(|e| VanityError::io(format!("cannot check job {}", job.id), e))?;
+        Ok(match exit {
+            None => JobStatus::Running,
+            Some(_) if local.cancelled => JobStatus::Cancelled,
+            Some(status) if status.success() => JobStatus::Succeeded,
+            Some(status) => JobStatus::Failed { reason: status.to_string() },
+        })
+    }
+
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError> {
+        let log = &self.job(job)?.log;
+        fs::read_to_string(log).map_err(|e| VanityError::io(format!("cannot read {}", log.display()), e))
+    }
+
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError> {
+        let local = self.job(job)?;
+        local.cancelled = true;
+        // SIGTERM lets the search flush its state file and print its summary
+        #[cfg(unix)]
+        {
+            // SAFETY: plain signal to a child we spawned and have not yet reaped
+            if unsafe { libc::kill(local.child.id() as libc::pid_t, libc::SIGTERM) } == 0 {
+                return Ok(());
+            }
+        }
+        match local.child.kill() {
+            Err(e) if e.kind() != io::ErrorKind::InvalidInput => {
+                Err(VanityError::io(format!("cannot stop job {}", job.id), e))
+            }
+            // InvalidInput: it had already exited
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Submits to AWS Batch with the `aws` CLI; the job definition's image must have
+/// `solana-vanity-seed` as its entrypoint (as the repo's Dockerfile does)
+pub struct AwsBatchExecutor {
+    queue: String,
+    job_definition: String,
+    limits: ResourceLimits,
+    runner: Box<dyn CommandRunner>,
+}
+
+impl AwsBatchExecutor {
+    pub fn new(queue: impl Into<String>, job_definition: impl Into<String>, runner: impl CommandRunner + 'static) -> Self {
+        AwsBatchExecutor {
+            queue: queue.into(),
+            job_definition: job_definition.into(),
+            limits: ResourceLimits::default(),
+            runner: Box::new(runner),
+        }
+    }
+
+    /// Override the job definition's vCPU, memory and GPU requirements
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn describe(&mut self, job: &JobHandle) -> Result<Value, VanityError> {
+        let stdout = self.runner.run(&strings(["aws", "batch", "describe-jobs", "--jobs", &job.id, "--output", "json"]))?;
+        let mut doc = parse_json("aws batch describe-jobs", &stdout)?;
+        match doc["jobs"].get_mut(0) {
+            Some(found) => Ok(found.take()),
+            None => Err(missing("aws batch describe-jobs", &format!("job {}", job.id))),
+        }
+    }
+}
+
+impl Executor for AwsBatchExecutor {
+    fn name(&self) -> String {
+        format!("aws-batch:{}", self.queue)
+    }
+
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>> {
+        let mut overrides = serde_json::json!({ "command": job.args });
+        let requirements: Vec<Value> = [
+            ("VCPU", self.limits.cpus.map(u64::from)),
+            ("MEMORY", self.limits.memory_mib),
+            ("GPU", self.limits.gpus.map(u64::from)),
+        ]
+        .into_iter()
+        .filter_map(|(kind, value)| value.map(|v| serde_json::json!({ "type": kind, "value": v.to_string() })))
+        .collect();
+        if !requirements.is_empty() {
+            overrides["resourceRequirements"] = Value::Array(requirements);
+        }
+        let overrides = overrides.to_string();
+        vec![strings([
+            "aws",
+            "batch",
+            "submit-job",
+            "--job-name",
+            &job.name,
+            "--job-queue",
+            &self.queue,
+            "--job-definition",
+            &self.job_definition,
+            "--container-overrides",
+            &overrides,
+            "--output",
+            "json",
+        ])]
+    }
+
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError> {
+        let stdout = self.runner.run(&self.plan(job)[0])?;
+        let doc = parse_json("aws batch submit-job", &stdout)?;
+        let id = doc["jobId"].as_str().ok_or_else(|| missing("aws batch submit-job", "jobId"))?;
+        Ok(JobHandle { executor: self.name(), id: id.to_string() })
+    }
+
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError> {
+        let found = self.describe(job)?;
+        let reason = found["statusReason"].as_str().unwrap_or("no reason given").to_string();
+        Ok(match found["status"].as_str().unwrap_or_default() {
+            "SUBMITTED" | "PENDING" | "RUNNABLE" | "STARTING" => JobStatus::Pending,
+            "RUNNING" => JobStatus::Running,
+            "SUCCEEDED" => JobStatus::Succeeded,
+            // Batch reports terminated jobs as FAILED with the reason given to terminate-job
+            "FAILED" if reason == CANCEL_REASON => JobStatus::Cancelled,
+            "FAILED" => JobStatus::Failed { reason },
+            other => JobStatus::Failed { reason: format!("unknown AWS Batch status {:?}", other) },
+        })
+    }
+
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError> {
+        let found = self.describe(job)?;
+        // No stream until the container has started
+        let Some(stream) = found["container"]["logStreamName"].as_str() else {
+            return Ok(String::new());
+        };
+        let mut logs = String::new();
+        let mut token: Option<String> = None;
+        // One page at a time; the last page hands back the token it was asked for
+        loop {
+            let mut argv = strings([
+                "aws",
+                "logs",
+                "get-log-events",
+                "--log-group-name",
+                "/aws/batch/job",
+                "--log-stream-name",
+                stream,
+                "--start-from-head",
+                "--output",
+                "json",
+            ]);
+            if let Some(token) = &token {
+                argv.extend(strings(["--next-token", token]));
+            }
+            let doc = parse_json("aws logs get-log-events", &self.runner.run(&argv)?)?;
+            let events = doc["events"].as_array().map(Vec::as_slice).unwrap_or_default();
+            for message in events.iter().filter_map(|e| e["message"].as_str()) {
+                logs.push_str(message);
+                logs.push('\n');
+            }
+            let next = doc["nextForwardToken"].as_str().map(str::to_string);
+            if next.is_none() || next == token {
+                return Ok(logs);
+            }
+            token = next;
+        }
+    }
+
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError> {
+        self.runner
+            .run(&strings(["aws", "batch", "terminate-job", "--job-id", &job.id, "--reason", CANCEL_REASON]))
+            .map(drop)
+    }
+}
+
+/// Reason recorded on AWS Batch jobs we terminate
+const CANCEL_REASON: &str = "cancelled by solana-vanity-seed";
+
+/// Executes an existing Cloud Run job with the `gcloud` CLI, passing the search as the container args
+pub struct CloudRunExecutor {
+    /// Image to switch the job to before executing, if any
+    image: Option<String>,
+    limits: ResourceLimits,
+    runner: Box<dyn CommandRunner>,
+    /// When each execution started by this executor was submitted, to bound its log query
+    submitted: HashMap<String, Instant>,
+}
+
+/// How far back to read logs of an execution this executor didn't submit: Cloud Run's longest
+/// task timeout (7 days) plus a day for queueing
+const MAX_LOG_FRESHNESS: &str = "8d";
+
+impl CloudRunExecutor {
+    pub fn new(image: Option<String>, runner: impl CommandRunner + 'static) -> Self {
+        CloudRunExecutor { image, limits: ResourceLimits::default(), runner: Box::new(runner), submitted: HashMap::new() }
+    }
+
+    /// Update the job's CPU, memory and GPU limits before executing
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// gcloud list flags split on commas unless given another delimiter as `^D^`
+fn gcloud_list(items: &[String]) -> Result<String, VanityError> {
+    if items.iter().all(|a| !a.contains(',')) {
+        return Ok(items.join(","));
+    }
+    ['|', ';', '~', '#', '!']
+        .into_iter()
+        .find(|d| items.iter().all(|a| !a.contains(*d)))
+        .map(|d| format!("^{d}^{}", items.join(&d.to_string())))
+        .ok_or_else(|| VanityError::InvalidInput("cannot pass these arguments to gcloud".to_string()))
+}
+
+impl Executor for CloudRunExecutor {
+    fn name(&self) -> String {
+        "cloud-run".to_string()
+    }
+
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>> {
+        let mut plan = Vec::new();
+        if self.image.is_some() || !self.limits.is_empty() {
+            let mut update = strings(["gcloud", "run", "jobs", "update", &job.name]);
+            let flags = [
+                ("--image", self.image.clone()),
+                ("--cpu", self.limits.cpus.map(|n| n.to_string())),
+                ("--memory", self.limits.memory_mib.map(|n| format!("{}Mi", n))),
+                ("--gpu", self.limits.gpus.map(|n| n.to_string())),
+            ];
+            for (flag, value) in flags {
+                if let Some(value) = value {
+                    update.extend([flag.to_string(), value]);
+                }
+            }
+            update.push("--quiet".to_string());
+            plan.push(update);
+        }
+        // `submit` rejects arguments no delimiter can separate
+        let args = gcloud_list(&job.args).unwrap_or_else(|_| job.args.join(","));
+        plan.push(strings([
+            "gcloud",
+            "run",
+            "jobs",
+            "execute",
+            &job.name,
+            &format!("--args={}", args),
+            "--async",
+            "--format=json",
+        ]));
+        plan
+    }
+
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError> {
+        gcloud_list(&job.args)?;
+        let mut stdout = String::new();
+        for argv in self.plan(job) {
+            stdout = self.runner.run(&argv)?;
+        }
+        let doc = parse_json("gcloud run jobs execute", &stdout)?;
+        let id = doc["metadata"]["name"].as_str().ok_or_else(|| missing("gcloud run jobs execute", "metadata.name"))?;
+        self.submitted.insert(id.to_string(), Instant::now());
+        Ok(JobHandle { executor: self.name(), id: id.to_string() })
+    }
+
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError> {
+        let stdout =
+            self.runner.run(&strings(["gcloud", "run", "jobs", "executions", "describe", &job.id, "--format=json"]))?;
+        let doc = parse_json("gcloud run jobs executions describe", &stdout)?;
+        let status = &doc["status"];
+        let count = |field: &str| status[field].as_u64().unwrap_or(0);
+        let completed = status["conditions"]
+            .as_array()
+            .and_then(|c| c.iter().find(|c| c["type"] == "Completed"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        Ok(match completed["status"].as_str() {
+            _ if count("cancelledCount") > 0 => JobStatus::Cancelled,
+            Some("True") => JobStatus::Succeeded,
+            Some("False") => JobStatus::Failed {
+                reason: completed["message"].as_str().unwrap_or("no reason given").to_string(),
+            },
+            _ if count("runningCount") > 0 => JobStatus::Running,
+            _ => JobStatus::Pending,
+        })
+    }
+
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError> {
+        let filter = format!(
+            "resource.type=\"cloud_run_job\" AND labels.\"run.googleapis.com/execution_name\"=\"{}\"",
+            job.id
+        );
+        // gcloud only reads the last day by default; cover the whole run, plus a minute for log delivery
+        let freshness = match self.submitted.get(&job.id) {
+            Some(at) => format!("--freshness={}s", at.elapsed().as_secs() + 60),
+            None => format!("--freshness={}", MAX_LOG_FRESHNESS),
+        };
+        self.runner.run(&strings([
+            "gcloud",
+            "logging",
+            "read",
+            &filter,
+            &freshness,
+            "--order=asc",
+            "--format=value(textPayload)",
+        ]))
+    }
+
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError> {
+        self.runner.run(&strings(["gcloud", "run", "jobs", "executions", "cancel", &job.id, "--quiet"])).map(drop)
+    }
+}
+
+/// Test doubles shared by the executors' tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::CommandRunner;
+    use crate::error::VanityError;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Replays canned stdout and records the commands it was asked to run
+    #[derive(Clone, Default)]
+    pub(crate) struct ScriptedRunner {
+        calls: Arc<Mutex<Vec<Vec<String>>>>,
+        replies: Arc<Mutex<VecDeque<String>>>,
+    }
+
+    impl ScriptedRunner {
+        pub(crate) fn replying(replies: &[&str]) -> Self {
+            let runner = ScriptedRunner::default();
+            runner.replies.lock().unwrap().extend(replies.iter().map(|r| r.to_string()));
+            runner
+        }
+
+        pub(crate) fn calls(&self) -> Vec<Vec<String>> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl CommandRunner for ScriptedRunner {
+        fn run(&mut self, argv: &[String]) -> Result<String, VanityError> {
+            self.calls.lock().unwrap().push(argv.to_vec());
+            Ok(self.replies.lock().unwrap().pop_front().expect("unexpected command"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::ScriptedRunner;
+    use super::*;
+
+    fn job(args: &[&str]) -> JobSpec {
+        JobSpec { name: "vanity".into(), args: args.iter().map(|a| a.to_string()).collect() }
+    }
+
+    #[test]
+    fn shell_quoting() {
+        assert_eq!(shell_quote("--prefix"), "--prefix");
+        assert_eq!(shell_quote("^ab(c|d)$"), "'^ab(c|d)$'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn aws_batch_round_trip() {
+        let runner = ScriptedRunner::replying(&[
+            r#"{"jobName":"vanity","jobId":"abc-123"}"#,
+            r#"{"jobs":[{"status":"RUNNABLE"}]}"#,
+            r#"{"jobs":[{"status":"FAILED","statusReason":"Essential container exited"}]}"#,
+            r#"{"jobs":[{"status":"SUCCEEDED","container":{"logStreamName":"vanity/default/1"}}]}"#,
+            r#"{"events":[{"message":"Public Address: X"}],"nextForwardToken":"f/1"}"#,
+            r#"{"events":[{"message":"done"}],"nextForwardToken":"f/2"}"#,
+            r#"{"events":[],"nextForwardToken":"f/2"}"#,
+            "{}",
+        ]);
+        let mut executor = AwsBatchExecutor::new("cpu-queue", "vanity-def", runner.clone());
+        let handle = executor.submit(&job(&["--prefix", "a b"])).unwrap();
+        assert_eq!(handle.id, "abc-123");
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Pending);
+        assert_eq!(
+            executor.status(&handle).unwrap(),
+            JobStatus::Failed { reason: "Essential container exited".into() }
+        );
+        assert_eq!(executor.logs(&handle).unwrap(), "Public Address: X\ndone\n");
+        executor.cancel(&handle).unwrap();
+        let calls = runner.calls();
+        // Arguments travel as a JSON array, never through a shell
+        assert_eq!(calls[0][10], r#"{"command":["--prefix","a b"]}"#);
+        assert_eq!(calls[4][6], "vanity/default/1");
+        // Log pages are followed until the forward token stops changing
+        assert!(!calls[4].contains(&"--next-token".to_string()));
+        assert_eq!(calls[5][10..], strings(["--next-token", "f/1"]));
+        assert_eq!(calls[6][10..], strings(["--next-token", "f/2"]));
+        assert_eq!(calls[7][..4], strings(["aws", "batch", "terminate-job", "--job-id"]));
+    }
+
+    #[test]
+    fn resource_limits_are_requested() {
+        let limits = ResourceLimits { cpus: Some(4), memory_mib: Some(8192), gpus: None };
+        let aws = AwsBatchExecutor::new("q", "def", ScriptedRunner::default()).limits(limits);
+        assert_eq!(
+            aws.plan(&job(&["--raw"]))[0][10],
+            r#"{"command":["--raw"],"resourceRequirements":[{"type":"VCPU","value":"4"},{"type":"MEMORY","value":"8192"}]}"#
+        );
+        let cloud_run = CloudRunExecutor::new(None, ScriptedRunner::default()).limits(limits);
+        assert_eq!(cloud_run.plan(&job(&["--raw"]))[0][5..], strings(["--cpu", "4", "--memory", "8192Mi", "--quiet"]));
+    }
+
+    #[test]
+    fn cloud_run_round_trip() {
+        let runner = ScriptedRunner::replying(&[
+            "",
+            r#"{"metadata":{"name":"vanity-x7k2p"}}"#,
+            r#"{"status":{"runningCount":1}}"#,
+            r#"{"status":{"conditions":[{"type":"Completed","status":"True"}],"succeededCount":1}}"#,
+            r#"{"status":{"cancelledCount":1,"conditions":[{"type":"Completed","status":"False"}]}}"#,
+            "found it",
+            "",
+        ]);
+        let mut executor = CloudRunExecutor::new(Some("gcr.io/p/vanity:1".into()), runner.clone());
+        let handle = executor.submit(&job(&["--regex", "^a{1,2}"])).unwrap();
+        assert_eq!(handle.id, "vanity-x7k2p");
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Running);
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Succeeded);
+        assert_eq!(executor.status(&handle).unwrap(), JobStatus::Cancelled);
+        assert_eq!(executor.logs(&handle).unwrap(), "found it");
+        let other = JobHandle { executor: executor.name(), id: "vanity-older".into() };
+        executor.logs(&other).unwrap();
+        let calls = runner.calls();
+        assert_eq!(calls[0][4..7], strings(["vanity", "--image", "gcr.io/p/vanity:1"]));
+        // Logs reach back to the submission, or as far as a job can run for one submitted elsewhere
+        assert!(calls[5][4].starts_with("--freshness=6") && calls[5][4].ends_with('s'), "{:?}", calls[5]);
+        assert_eq!(calls[6][4], "--freshness=8d");
+        // The comma in the regex forces another list delimiter
+        assert_eq!(calls[1][5], "--args=^|^--regex|^a{1,2}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn local_process_lifecycle() {
+        let dir = std::env::temp_dir().join(format!("vanity-exec-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut executor = LocalProcessExecutor::new("/bin/sh", &dir);
+        let failing = executor.submit(&job(&["-c", "echo hello; exit 3"])).unwrap();
+        let sleeping = executor.submit(&job(&["-c", "sleep 30"])).unwrap();
+        while !executor.status(&failing).unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(matches!(executor.status(&failing).unwrap(), JobStatus::Failed { .. }));
+        assert_eq!(executor.logs(&failing).unwrap(), "hello\n");
+        assert_eq!(executor.status(&sleeping).unwrap(), JobStatus::Running);
+        executor.cancel(&sleeping).unwrap();
+        while !executor.status(&sleeping).unwrap().is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(executor.status(&sleeping).unwrap(), JobStatus::Cancelled);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
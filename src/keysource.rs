@@ -0,0 +1,151 @@
+use crate::search::GenerationMode;
+use crate::seed::{mnemonic_seed, mnemonic_seeds, LANES};
+use bip39::{Language, Mnemonic};
+use rand::RngCore;
+use solana_sdk::signature::{Keypair, SeedDerivable};
+
+/// A candidate key: the keypair whose address is matched plus any secret it was derived from
+pub struct Candidate {
+    pub keypair: Keypair,
+    /// Mnemonic the keypair was derived from; rendered to a phrase only for a match
+    pub mnemonic: Option<Mnemonic>,
+}
+
+/// Produces candidate keys for the search loop.
+///
+/// Implementations are shared by all worker threads, so any per-call state must be created
+/// inside `generate` (or kept in thread-locals). All randomness must come from `rng` so that
+/// seeded searches are reproducible.
+pub trait KeySource: Send + Sync {
+    /// Generate the next candidate
+    fn generate(&self, rng: &mut dyn RngCore) -> Candidate;
+
+    /// Candidates the search loop asks `generate_batch` for at a time; sources that derive
+    /// several keys at once (e.g. in SIMD lanes) return more than 1
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    /// Generate one candidate from each RNG, in order
+    fn generate_batch(&self, rngs: &mut [&mut dyn RngCore]) -> Vec<Candidate> {
+        rngs.iter_mut().map(|rng| self.generate(*rng)).collect()
+    }
+
+    /// Name recorded in state files and structured output (e.g. "raw", "mnemonic")
+    fn name(&self) -> &str;
+
+    /// Whether the private key should be reported for a match (token mint searches only report the address)
+    fn reveals_secret(&self) -> bool {
+        true
+    }
+}
+
+/// ED25519 keypair from a random 32-byte secret seed
+fn random_keypair(rng: &mut dyn RngCore) -> Keypair {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    Keypair::from_seed(&seed).unwrap()
+}
+
+/// Random ED25519 keypairs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RawKeySource;
+
+impl KeySource for RawKeySource {
+    fn generate(&self, rng: &mut dyn RngCore) -> Candidate {
+        Candidate { keypair: random_keypair(rng), mnemonic: None }
+    }
+
+    fn name(&self) -> &str {
+        "raw"
+    }
+}
+
+/// Keypairs derived from random BIP-39 mnemonics (first 32 bytes of the seed, as solana-keygen does)
+#[derive(Debug, Clone, Copy)]
+pub struct MnemonicKeySource {
+    words: usize,
+}
+
+impl MnemonicKeySource {
+    /// `words` must be 12 or 24
+    pub fn new(words: usize) -> Self {
+        MnemonicKeySource { words }
+    }
+
+    fn mnemonic(&self, rng: &mut dyn RngCore) -> Mnemonic {
+        let mut entropy = [0u8; 32];
+        let entropy = &mut entropy[..if self.words == 12 { 16 } else { 32 }];
+        rng.fill_bytes(entropy);
+        Mnemonic::from_entropy_in(Language::English, entropy).unwrap()
+    }
+}
+
+impl KeySource for MnemonicKeySource {
+    fn generate(&self, rng: &mut dyn RngCore) -> Candidate {
+        let mnemonic = self.mnemonic(rng);
+        let keypair = Keypair::from_seed(&mnemonic_seed(&mnemonic)[..32]).unwrap();
+        Candidate { keypair, mnemonic: Some(mnemonic) }
+    }
+
+    fn batch_size(&self) -> usize {
+        LANES
+    }
+
+    /// PBKDF2 for the whole batch runs side by side in SIMD lanes
+    fn generate_batch(&self, rngs: &mut [&mut dyn RngCore]) -> Vec<Candidate> {
+        let mnemonics: Vec<Mnemonic> = rngs.iter_mut().map(|rng| self.mnemonic(*rng)).collect();
+        let seeds = mnemonic_seeds(&mnemonics);
+        mnemonics
+            .into_iter()
+            .zip(seeds)
+            .map(|(mnemonic, seed)| Candidate {
+                keypair: Keypair::from_seed(&seed[..32]).unwrap(),
+                mnemonic: Some(mnemonic),
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "mnemonic"
+    }
+}
+
+/// Token mint addresses: raw keypairs where only the address is reported
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokenKeySource;
+
+impl KeySource for TokenKeySource {
+    fn generate(&self, rng: &mut dyn RngCore) -> Candidate {
+        Candidate { keypair: random_keypair(rng), mnemonic: None }
+    }
+
+    fn name(&self) -> &str {
+        "token"
+    }
+
+    fn reveals_secret(&self) -> bool {
+        false
+    }
+}
+
+impl GenerationMode {
+    /// Parse the name recorded in `SearchConfig::generation` ("raw", "mnemonic" or "token")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(GenerationMode::Raw),
+            "mnemonic" => Some(GenerationMode::Mnemonic),
+            "token" => Some(GenerationMode::Token),
+            _ => None,
+        }
+    }
+
+    /// The built-in key source for this mode
+    pub fn key_source(self, words: usize) -> Box<dyn KeySource> {
+        match self {
+            GenerationMode::Raw => Box::new(RawKeySource),
+            GenerationMode::Mnemonic => Box::new(MnemonicKeySource::new(words)),
+            GenerationMode::Token => Box::new(TokenKeySource),
+        }
+    }
+}
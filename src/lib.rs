@@ -0,0 +1,150 @@
+//! Solana vanity address search.
+//!
+//! The `solana-vanity-seed` binary is a thin CLI over this crate; services can embed the same
+//! search with [`VanitySearch`] instead of shelling out.
+
+mod breakdown;
+mod cluster;
+mod container;
+mod cost;
+mod error;
+mod executor;
+mod keysource;
+mod matcher;
+mod placement;
+mod profile;
+mod queue;
+mod render;
+mod search;
+mod seed;
+mod server;
+mod sink;
+mod ssh;
+mod state;
+
+pub use breakdown::{candidate_breakdown, StageTiming};
+pub use cluster::{WorkerOutcome, PROTOCOL_VERSION};
+pub use container::{ContainerEngine, ContainerExecutor, DEFAULT_IMAGE, RESULT_FILE};
+pub use cost::{attempts_for_probability, CostModel, Estimate};
+pub use error::VanityError;
+pub use executor::{
+    shell_join, shell_quote, AwsBatchExecutor, CloudRunExecutor, CommandRunner, Executor, JobHandle, JobProgress, JobSpec,
+    JobStatus, LocalProcessExecutor, ResourceLimits, SystemRunner,
+};
+pub use keysource::{Candidate, KeySource, MnemonicKeySource, RawKeySource, TokenKeySource};
+pub use matcher::{AllOf, AnyOf, BothMatcher, Matcher, PrefixMatcher, RegexMatcher, SuffixMatcher};
+pub use placement::ThreadPlacement;
+pub use profile::{Profile, ProfileConfig, ProfileKind, DEFAULT_CONFIG_FILE};
+pub use queue::{ApiError, JobRequest, JobView, ResultState, SearchService};
+pub use render::{render_job, FanOut, JobTarget};
+pub use search::{
+    probability_after, GenerationMode, Hit, Progress, SearchEvent, SearchLimits, SearchMode, VanitySearch,
+};
+pub use seed::{mnemonic_seed, mnemonic_seeds, LANES};
+pub use server::serve;
+pub use sink::{
+    decrypt_result_file, deliver_all, EncryptedFileSink, Event, JsonLinesSink, KeypairFileSink, OutputFormat,
+    ResultSink, SearchResult, StoredSecret, TerminalSink, WebhookSink,
+};
+pub use ssh::{RemoteBinary, SshExecutor, SshHost};
+pub use state::{Checkpoint, FoundKey, SearchConfig, SearchState};
+
+/// The Base58 alphabet used by Solana addresses
+pub const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Check that a prefix/suffix pattern is non-empty and uses only Base58 characters
+pub fn validate_pattern(pat: &str) -> Result<(), VanityError> {
+    if pat.is_empty() {
+        return Err(VanityError::InvalidInput("pattern must not be empty".to_string()));
+    }
+    match pat.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+        Some(c) => Err(VanityError::InvalidInput(format!(
+            "Invalid character '{}' in pattern\nAllowed Base58 alphabet: {}",
+            c, BASE58_ALPHABET
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Format seconds into a human-readable string
+pub fn format_duration(secs: f64) -> String {
+    let s = secs.round() as u64;
+    let days = s / 86_400;
+    let hours = (s % 86_400) / 3_600;
+    let mins = (s % 3_600) / 60;
+    let secs = s % 60;
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{}d", days));
+    }
+    if hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    if mins > 0 {
+        parts.push(format!("{}m", mins));
+    }
+    parts.push(format!("{}s", secs));
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn format_duration_omits_empty_units() {
+        assert_eq!(format_duration(0.0), "0s");
+        assert_eq!(format_duration(0.4), "0s");
+        assert_eq!(format_duration(59.6), "1m 0s");
+        assert_eq!(format_duration(61.0), "1m 1s");
+        assert_eq!(format_duration(3_600.0), "1h 0s");
+        assert_eq!(format_duration(90_061.0), "1d 1h 1m 1s");
+        assert_eq!(format_duration(86_400.0 * 400.0), "400d 0s");
+    }
+
+    #[test]
+    fn validate_pattern_checks_alphabet() {
+        assert!(validate_pattern("Sun").is_ok());
+        assert!(validate_pattern(BASE58_ALPHABET).is_ok());
+        for bad in ["", "0", "O", "I", "l", "Sun!", "Sün", "a b"] {
+            assert!(matches!(validate_pattern(bad), Err(VanityError::InvalidInput(_))), "{:?}", bad);
+        }
+        let err = validate_pattern("ab0").unwrap_err().to_string();
+        assert!(err.contains("'0'"), "{}", err);
+    }
+
+    proptest! {
+        #[test]
+        fn format_duration_adds_up(secs in 0u64..10_000_000) {
+            let total: u64 = format_duration(secs as f64)
+                .split(' ')
+                .map(|part| {
+                    let (n, unit) = part.split_at(part.len() - 1);
+                    let scale = match unit {
+                        "d" => 86_400,
+                        "h" => 3_600,
+                        "m" => 60,
+                        _ => 1,
+                    };
+                    n.parse::<u64>().unwrap() * scale
+                })
+                .sum();
+            prop_assert_eq!(total, secs);
+        }
+
+        #[test]
+        fn validate_pattern_accepts_base58(pattern in "[1-9A-HJ-NP-Za-km-z]{1,12}") {
+            prop_assert!(validate_pattern(&pattern).is_ok());
+        }
+
+        #[test]
+        fn validate_pattern_rejects_other_characters(
+            pattern in "[1-9A-HJ-NP-Za-km-z]{0,6}",
+            bad in "[0OIl+/ _-]",
+        ) {
+            let pattern = format!("{}{}", pattern, bad);
+            prop_assert!(validate_pattern(&pattern).is_err());
+        }
+    }
+}
@@ -1,24 +1,39 @@
-use bip39::{Language, Mnemonic};
-use solana_sdk::signature::{Keypair, SeedDerivable, Signer};
-use clap::{Parser, ValueEnum};
-use rayon::prelude::*;
-use bs58;
-use rand::{thread_rng, RngCore};
-use num_cpus;
-
-// Define the Base58 alphabet for validation
-const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::thread_rng;
+use solana_vanity_seed::{
+    candidate_breakdown, decrypt_result_file, format_duration, render_job, serve, shell_join, Checkpoint,
+    EncryptedFileSink, Event, Executor, FanOut, GenerationMode, JobSpec, JobStatus, JobTarget, JsonLinesSink, KeySource,
+    KeypairFileSink, Matcher, OutputFormat, PrefixMatcher, Profile, ProfileConfig, ProfileKind, Progress, RawKeySource,
+    ResourceLimits, ResultSink, SearchConfig, SearchEvent, SearchLimits, SearchMode, SearchService, SshHost, SystemRunner,
+    TerminalSink, ThreadPlacement, VanityError, VanitySearch, WebhookSink, WorkerOutcome, BASE58_ALPHABET,
+    DEFAULT_CONFIG_FILE,
+};
+use std::cell::Cell;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 /// Which executor to dispatch the job to (trade-off between cost and speed)
 #[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
-enum Executor {
+enum ExecutorKind {
     /// Run locally (free, CPU-only)
     Local,
+    /// Local background process, tracked like a remote job (output in a log file)
+    Process,
     /// Remote CPU cluster (~$0.10/hr)
     Cpu,
     /// GCP A100 GPU (fast, cost-effective GPU)
     GcpGpu,
     /// AWS GPU (top-tier speed, higher cost)
     AwsGpu,
+    /// Your own machines over SSH (--ssh-host)
+    Ssh,
+    /// Local Docker container running the cloud CPU image (built from ./Dockerfile unless --container-image)
+    Docker,
+    /// Like docker, with Podman
+    Podman,
 }
 
 #[derive(Parser)]
@@ -33,6 +48,9 @@ struct Args {
     /// Calibrate key-generation speed and estimate search times
     #[clap(long, conflicts_with = "interactive")]
     calibrate: bool,
+    /// Break down where per-candidate time goes for the selected generation mode and pattern
+    #[clap(long, conflicts_with_all = ["interactive", "calibrate"])]
+    bench: bool,
     /// Include total run time in final search output
     #[clap(long, conflicts_with = "interactive")]
     time: bool,
@@ -42,6 +60,9 @@ struct Args {
     /// Vanity suffix (Base58) to search for
     #[clap(long, value_parser)]
     suffix: Option<String>,
+    /// Regular expression the address must match (combined with --prefix/--suffix if given)
+    #[clap(long, value_parser)]
+    regex: Option<String>,
     /// Generate raw ED25519 keypairs (private key output)
     #[clap(long, conflicts_with = "token")]
     raw: bool,
@@ -54,9 +75,24 @@ struct Args {
     /// Number of CPU threads to use; defaults to all logical cores
     #[clap(long)]
     threads: Option<usize>,
-    /// Which executor to use: local, cpu, gcp-gpu, or aws-gpu
-    #[clap(long, value_enum, default_value_t = Executor::Local)]
-    executor: Executor,
+    /// Run search threads at this niceness (-20..=19; e.g. 19 so a background search yields to other work)
+    #[clap(long, allow_hyphen_values = true)]
+    nice: Option<i32>,
+    /// Pin search threads to these CPUs, round-robin (e.g. 0-3,6; Linux only)
+    #[clap(long, value_name = "LIST", value_parser = parse_cpu_list)]
+    pin_cpus: Option<CpuList>,
+    /// Which executor to use: local, process, cpu, gcp-gpu, aws-gpu, ssh, docker or podman
+    #[clap(long, value_enum, default_value_t = ExecutorKind::Local)]
+    executor: ExecutorKind,
+    /// Submit the job with this profile from the config file instead of --executor
+    #[clap(long, conflicts_with = "executor")]
+    profile: Option<String>,
+    /// Config file with executor profiles (default: vanity.toml in the working directory, if any)
+    #[clap(long, value_parser)]
+    config: Option<PathBuf>,
+    /// Print the commands that would submit the job to --executor or --profile instead of running them
+    #[clap(long)]
+    dry_run: bool,
     /// Remote CPU cluster job name (for --executor cpu)
     #[clap(long, default_value = "vanity-search-cpu")]
     cpu_job: String,
@@ -66,15 +102,200 @@ struct Args {
     /// GCP GPU job name (for --executor gcp-gpu)
     #[clap(long, default_value = "vanity-gpu-job")]
     gcp_gpu_job: String,
-    /// GCP GPU container image (for --executor gcp-gpu)
-    #[clap(long, default_value = "gcr.io/myproj/vanity-gpu:latest")]
-    gcp_gpu_image: String,
+    /// Switch the GCP GPU job to this container image before running it (for --executor gcp-gpu)
+    #[clap(long)]
+    gcp_gpu_image: Option<String>,
+    /// Measured search rate of the GCP GPU job; without it the tier is left out of cost estimates
+    #[clap(long, value_name = "KEYS_PER_SEC", value_parser = parse_rate)]
+    gcp_gpu_rate: Option<f64>,
     /// AWS GPU job name (for --executor aws-gpu)
     #[clap(long, default_value = "vanity-search-gpu")]
     aws_gpu_job: String,
     /// AWS GPU job queue (for --executor aws-gpu)
     #[clap(long, default_value = "gpu-queue")]
     aws_gpu_queue: String,
+    /// Measured search rate of the AWS GPU job; without it the tier is left out of cost estimates
+    #[clap(long, value_name = "KEYS_PER_SEC", value_parser = parse_rate)]
+    aws_gpu_rate: Option<f64>,
+    /// SSH host for --executor ssh, as DEST or DEST=THREADS (repeat for more hosts)
+    #[clap(long = "ssh-host", value_name = "DEST[=THREADS]")]
+    ssh_hosts: Vec<SshHost>,
+    /// Copy this binary to the SSH hosts instead of running their installed solana-vanity-seed
+    #[clap(long)]
+    ssh_upload: bool,
+    /// Run this image for --executor docker/podman instead of building one from ./Dockerfile
+    #[clap(long)]
+    container_image: Option<String>,
+    /// CPUs the container may use (for --executor docker/podman)
+    #[clap(long, value_name = "N")]
+    container_cpus: Option<u32>,
+    /// Memory the container may use, in MiB (for --executor docker/podman)
+    #[clap(long, value_name = "MIB")]
+    container_memory: Option<u64>,
+    /// Periodically record search progress and results to this file
+    #[clap(long, value_parser)]
+    state_file: Option<PathBuf>,
+    /// Continue the search recorded in --state-file (starts fresh if the file is missing)
+    #[clap(long, requires = "state_file")]
+    resume: bool,
+    /// Seconds between state-file checkpoints
+    #[clap(long, default_value_t = 60)]
+    checkpoint_interval: u64,
+    /// Seconds between progress notifications while searching
+    #[clap(long, default_value_t = 10)]
+    progress_interval: u64,
+    /// Give up after this much wall-clock time (e.g. 90s, 45m, 6h, 2d, 1h30m)
+    #[clap(long, value_parser = parse_duration)]
+    max_time: Option<Duration>,
+    /// Give up after this many candidate keys in this run
+    #[clap(long)]
+    max_attempts: Option<u64>,
+    /// Output format: human-readable text, a single JSON result, or streamed JSON lines
+    #[clap(long, default_value = "text")]
+    format: OutputFormat,
+    /// Write the found keypair to this file (solana-keygen JSON) instead of printing the private key
+    #[clap(long, value_parser)]
+    outfile: Option<PathBuf>,
+    /// Also store the result in: keypair:PATH, jsonl:PATH, encrypted:PATH or webhook:URL (repeatable)
+    #[clap(long = "sink", value_name = "SPEC")]
+    sinks: Vec<String>,
+    /// Decrypt a file written by an encrypted: sink (passphrase from VANITY_PASSPHRASE) and exit
+    #[clap(long, value_name = "PATH")]
+    decrypt: Option<PathBuf>,
+    /// INSECURE: derive every candidate from this seed so runs are reproducible (testing only)
+    #[clap(long, hide = true)]
+    rng_seed: Option<u64>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Pool several machines for one search (plain TCP: use a trusted network or an SSH tunnel)
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the search described by the options before this subcommand to workers, and report the match
+    Coordinator {
+        /// Address to listen on for workers (e.g. 0.0.0.0:7878)
+        #[clap(long)]
+        listen: String,
+    },
+    /// Join a coordinator and search its job with this machine's --threads, --nice and --pin-cpus
+    Worker {
+        /// Coordinator address (host:port)
+        #[clap(long)]
+        connect: String,
+        /// Name shown in the coordinator's log (default: this worker's address)
+        #[clap(long)]
+        name: Option<String>,
+    },
+    /// Print a job definition that runs the search described by the options before this subcommand
+    RenderJob {
+        /// Scheduler to render for
+        #[clap(long, value_enum)]
+        target: RenderTarget,
+        /// Container image whose entrypoint is solana-vanity-seed (default: the profile's image)
+        #[clap(long)]
+        image: Option<String>,
+        /// Copies of the search to run at once (kubernetes and cloud-run)
+        #[clap(long, default_value_t = 1)]
+        parallelism: u32,
+        /// Copies to start in total (default: --parallelism); the first match ends a kubernetes job
+        #[clap(long)]
+        completions: Option<u32>,
+    },
+    /// Run searches submitted over a local HTTP/JSON API (each job brings its own patterns and limits)
+    Serve {
+        /// Address to listen on; anything but loopback requires VANITY_API_TOKEN
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Searches to run at once; further jobs wait in the queue by priority
+        #[clap(long, default_value_t = 1)]
+        max_jobs: usize,
+        /// Keep the queue, checkpoints and unfetched results in DIR, and resume them on restart
+        #[clap(long, value_name = "DIR")]
+        store: Option<PathBuf>,
+    },
+}
+
+/// Job formats `render-job` can emit
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum RenderTarget {
+    /// Kubernetes batch/v1 Job (kubectl apply -f)
+    Kubernetes,
+    /// AWS Batch job definition (aws batch register-job-definition --cli-input-json)
+    AwsBatch,
+    /// Cloud Run job (gcloud run jobs replace)
+    CloudRun,
+}
+
+impl From<RenderTarget> for JobTarget {
+    fn from(target: RenderTarget) -> Self {
+        match target {
+            RenderTarget::Kubernetes => JobTarget::Kubernetes,
+            RenderTarget::AwsBatch => JobTarget::AwsBatch,
+            RenderTarget::CloudRun => JobTarget::CloudRun,
+        }
+    }
+}
+
+/// Environment variable holding the passphrase for encrypted result files
+const PASSPHRASE_VAR: &str = "VANITY_PASSPHRASE";
+
+/// Environment variable holding the shared secret workers must present to the coordinator
+const CLUSTER_TOKEN_VAR: &str = "VANITY_CLUSTER_TOKEN";
+
+fn cluster_token() -> Option<String> {
+    std::env::var(CLUSTER_TOKEN_VAR).ok().filter(|t| !t.is_empty())
+}
+
+/// Environment variable holding the bearer token API clients must present
+const API_TOKEN_VAR: &str = "VANITY_API_TOKEN";
+
+/// Serve the search API on `listen` until Ctrl-C, then stop every job's search (stored jobs resume on restart)
+fn run_server(listen: &str, service: SearchService) -> Result<(), VanityError> {
+    let token = std::env::var(API_TOKEN_VAR).ok().filter(|t| !t.is_empty());
+    let listener = TcpListener::bind(listen).map_err(|e| VanityError::io(format!("cannot listen on {}", listen), e))?;
+    let addr = listener.local_addr().map_err(|e| VanityError::io(format!("cannot listen on {}", listen), e))?;
+    if !addr.ip().is_loopback() && token.is_none() {
+        return Err(VanityError::InvalidInput(format!(
+            "set {} before serving results (private keys) beyond this machine",
+            API_TOKEN_VAR
+        )));
+    }
+    eprintln!("Serving the search API on http://{} (Ctrl-C to stop)", addr);
+    let service = Arc::new(service);
+    service.start();
+    let stop = install_stop_handler();
+    let served = serve(Arc::clone(&service), listener, token, &stop);
+    service.shutdown();
+    served
+}
+
+fn passphrase() -> Result<String, VanityError> {
+    match std::env::var(PASSPHRASE_VAR) {
+        Ok(p) if !p.is_empty() => Ok(p),
+        _ => Err(VanityError::InvalidInput(format!("set {} to the passphrase for encrypted results", PASSPHRASE_VAR))),
+    }
+}
+
+/// Build a result sink from a `--sink` spec such as `jsonl:found.jsonl`
+fn parse_sink(spec: &str) -> Result<Box<dyn ResultSink>, VanityError> {
+    let invalid = || {
+        VanityError::InvalidInput(format!(
+            "invalid --sink '{}' (use keypair:PATH, jsonl:PATH, encrypted:PATH or webhook:URL)",
+            spec
+        ))
+    };
+    let (kind, target) = spec.split_once(':').ok_or_else(invalid)?;
+    if target.is_empty() {
+        return Err(invalid());
+    }
+    Ok(match kind {
+        "keypair" => Box::new(KeypairFileSink { path: target.into() }),
+        "jsonl" => Box::new(JsonLinesSink { path: target.into() }),
+        "encrypted" => Box::new(EncryptedFileSink { path: target.into(), passphrase: passphrase()? }),
+        "webhook" => Box::new(WebhookSink::new(target)?),
+        _ => return Err(invalid()),
+    })
 }
 
 fn parse_word_count(s: &str) -> Result<usize, String> {
@@ -85,6 +306,80 @@ fn parse_word_count(s: &str) -> Result<usize, String> {
         Err("Words must be 12 or 24".to_string())
     }
 }
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        _ => Err(format!("Invalid rate '{}' (keys per second, above 0)", s)),
+    }
+}
+
+/// Parse a duration such as `90`, `90s`, `45m`, `6h`, `2d` or `1h30m` (bare numbers are seconds)
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Ok(secs) = s.parse::<u64>() {
+        // As with `0s`, an empty budget is a mistake
+        return if secs == 0 { Err(format!("Invalid duration '{}'", s)) } else { Ok(Duration::from_secs(secs)) };
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            _ => return Err(format!("Invalid duration unit '{}' (use s, m, h or d)", c)),
+        };
+        let n: u64 = digits.parse().map_err(|_| format!("Invalid duration '{}'", s))?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| format!("Duration '{}' is too large", s))?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(format!("Invalid duration '{}'", s));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// CPUs from `--pin-cpus`; an alias so clap parses the whole list as one value
+type CpuList = Vec<usize>;
+
+/// CPU ids an affinity mask can hold
+#[cfg(target_os = "linux")]
+const CPU_SETSIZE: usize = libc::CPU_SETSIZE as usize;
+#[cfg(not(target_os = "linux"))]
+const CPU_SETSIZE: usize = 1024;
+
+/// Parse a CPU list such as `0-3,6` (ranges are inclusive)
+fn parse_cpu_list(s: &str) -> Result<CpuList, String> {
+    let mut cpus = Vec::new();
+    for part in s.split(',').map(str::trim) {
+        let bad = || format!("Invalid CPU list '{}' (use e.g. 0-3,6)", s);
+        let (first, last): (usize, usize) = match part.split_once('-') {
+            Some((a, b)) => (a.trim().parse().map_err(|_| bad())?, b.trim().parse().map_err(|_| bad())?),
+            None => {
+                let cpu = part.parse().map_err(|_| bad())?;
+                (cpu, cpu)
+            }
+        };
+        if first > last {
+            return Err(bad());
+        }
+        // Checked before expanding, so a huge range cannot exhaust memory
+        if last >= CPU_SETSIZE {
+            return Err(format!("CPU {} is out of range (CPU ids are below {})", last, CPU_SETSIZE));
+        }
+        cpus.extend(first..=last);
+    }
+    Ok(cpus)
+}
 // -- Interactive wizard support ------------------------------------------------
 use std::io::{self, Write};
 use std::time::Instant;
@@ -96,25 +391,11 @@ enum ProductType {
     Token,
 }
 
-/// Whether to search by prefix or suffix
-#[derive(Debug)]
-enum SearchMode {
-    Prefix(String),
-    Suffix(String),
-    /// Search for both a prefix and a suffix
-    Both { prefix: String, suffix: String },
-}
-
-/// Generation type for interactive mode: raw keypair, mnemonic, or token address only
-#[derive(Debug)]
-enum GenerationMode {
-    Raw,
-    Mnemonic,
-    Token,
-}
+/// Binary the wizard's local commands run (as built by `cargo build --release`)
+const WIZARD_PROGRAM: &str = "./target/release/solana-vanity-seed";
 
 /// Interactive wizard to collect options, estimate run time, and print the final command
-fn interactive_mode(time: bool) {
+fn interactive_mode(time: bool, executors: &ExecutorFlags, tiers: &[(String, Profile)]) {
     println!("Welcome to the Solana Vanity Address Wizard!");
     // Product selection: Wallet or Token
     let product = loop {
@@ -201,34 +482,21 @@ fn interactive_mode(time: bool) {
         println!("\nCalibrating mint address generation speed...");
         let sample = 1_000;
         let start = Instant::now();
-        for _ in 0..sample { generate_candidate(&mode, 0, true); }
+        let source = GenerationMode::Token.key_source(0);
+        for _ in 0..sample { source.generate(&mut thread_rng()); }
         let elapsed = start.elapsed();
         let per_thread = sample as f64 / elapsed.as_secs_f64();
         let total_rate = per_thread * threads as f64;
         // Estimate
-        let pat_len = match &mode {
-            SearchMode::Prefix(p) => p.len(),
-            SearchMode::Suffix(s) => s.len(),
-            SearchMode::Both { prefix, suffix } => prefix.len() + suffix.len(),
-        };
-        let space = (BASE58_ALPHABET.len() as f64).powi(pat_len as i32);
+        let pat_len = mode.pattern_len();
+        let space = expected_attempts(&mode);
         println!("\nEstimated total rate: {:.2} keys/sec", total_rate);
-        println!("Search space: 58^{} ≈ {:.0} keys", pat_len, space);
+        println!("Search space: 58^{} with case rules ≈ {:.0} keys", pat_len, space);
         println!("Avg time: {}", format_duration(space / total_rate));
+        print_cost_table(tiers, per_thread, threads, space);
         // Final command
         // Final command for token mint search
         println!("\n⭐ Build your token mint search command:");
-        // Build base invocation
-        let mut cmd = format!("./target/release/solana-vanity-seed --threads {} ", threads);
-        if time {
-            cmd.push_str("--time ");
-        }
-        cmd.push_str("--token ");
-        match &mode {
-            SearchMode::Prefix(p) => cmd.push_str(&format!("--prefix {} ", p)),
-            SearchMode::Suffix(s) => cmd.push_str(&format!("--suffix {} ", s)),
-            SearchMode::Both { prefix, suffix } => cmd.push_str(&format!("--prefix {} --suffix {} ", prefix, suffix)),
-        }
         // Present executor options
         println!("Executor options:");
         println!("  L) Local: slowest but free (runs on your machine)");
@@ -240,19 +508,17 @@ fn interactive_mode(time: bool) {
         let mut ex_in = String::new();
         io::stdin().read_line(&mut ex_in).unwrap();
         let exec = match ex_in.trim().to_uppercase().as_str() {
-            "C" => Executor::Cpu,
-            "G" => Executor::GcpGpu,
-            "A" => Executor::AwsGpu,
-            _   => Executor::Local,
-        };
-        // Wrap accordingly
-        let final_cmd = match exec {
-            Executor::Cpu    => format!("aws batch submit-job --job-name vanity-search-cpu --job-queue cpu-queue --container-overrides command=[\"sh\",\"-c\",\"{}\"]", cmd),
-            Executor::GcpGpu => format!("gcloud run jobs execute vanity-gpu-job --image gcr.io/myproj/vanity-gpu:latest --args=\"{}\"", cmd),
-            Executor::AwsGpu => format!("aws batch submit-job --job-name vanity-search-gpu --job-queue gpu-queue --container-overrides command=[\"sh\",\"-c\",\"{}\"]", cmd),
-            Executor::Local  => cmd.clone(),
+            "C" => ExecutorKind::Cpu,
+            "G" => ExecutorKind::GcpGpu,
+            "A" => ExecutorKind::AwsGpu,
+            _   => ExecutorKind::Local,
         };
-        println!("➜ {}", final_cmd);
+        let (prefix, suffix) = wizard_patterns(&mode);
+        let patterns = [("--prefix", &prefix), ("--suffix", &suffix)];
+        let args = search_args(GenerationMode::Token, 0, &patterns, SearchLimits::default(), time);
+        for command in executors.commands(exec, WIZARD_PROGRAM, &args, threads) {
+            println!("➜ {}", command);
+        }
         // Post steps
         println!("\nPost-generation steps for your new token:");
         println!("1. Run the above command and note the 'Public Address' value as your token mint address.");
@@ -371,51 +637,30 @@ fn interactive_mode(time: bool) {
     println!("\nCalibrating key generation speed (this may take a moment)...");
     let sample = 1_000;
     let start = Instant::now();
-    // Treat token mode same as raw for calibration
-    let raw_flag = matches!(gen_mode, GenerationMode::Raw | GenerationMode::Token);
+    let source = gen_mode.key_source(words);
     for _ in 0..sample {
-        generate_candidate(&mode, words, raw_flag);
+        source.generate(&mut thread_rng());
     }
     let elapsed = start.elapsed();
     let per_thread_rate = sample as f64 / elapsed.as_secs_f64();
     let total_rate = per_thread_rate * threads as f64;
     // Estimate search space
-    let pattern_len = match &mode {
-        SearchMode::Prefix(p) => p.len(),
-        SearchMode::Suffix(s) => s.len(),
-        SearchMode::Both { prefix, suffix } => prefix.len() + suffix.len(),
-    };
-    let avg_tries = (BASE58_ALPHABET.len() as f64).powi(pattern_len as i32);
+    let pattern_len = mode.pattern_len();
+    let avg_tries = expected_attempts(&mode);
     let avg_secs = avg_tries / total_rate;
     let best_secs = 1.0 / total_rate;
     let worst_secs = avg_secs * 5.0;
     println!("\nEstimated performance:");
     println!("  Key rate per thread: {:.2} keys/sec", per_thread_rate);
     println!("  Total rate ({} threads): {:.2} keys/sec", threads, total_rate);
-    println!("  Search space: 58^{} ≈ {:.0} keys", pattern_len, avg_tries);
+    println!("  Search space: 58^{} with case rules ≈ {:.0} keys", pattern_len, avg_tries);
     println!("  Best-case (lucky first hit): {}", format_duration(best_secs));
     println!("  Average-case: {}", format_duration(avg_secs));
     println!("  Very likely (<5× avg): {}", format_duration(worst_secs));
+    print_cost_table(tiers, per_thread_rate, threads, avg_tries);
     // Final command
     // Final command for wallet address search
     println!("\n⭐ Build your address search command:");
-    // Build base invocation
-    let mut cmd = format!("./target/release/solana-vanity-seed --threads {} ", threads);
-    if time {
-        cmd.push_str("--time ");
-    }
-    // Generation mode flags
-    match gen_mode {
-        GenerationMode::Raw => cmd.push_str("--raw "),
-        GenerationMode::Token => cmd.push_str("--token "),
-        GenerationMode::Mnemonic => cmd.push_str(&format!("--words {} ", words)),
-    }
-    // Search mode flags
-    match &mode {
-        SearchMode::Prefix(p) => cmd.push_str(&format!("--prefix {} ", p)),
-        SearchMode::Suffix(s) => cmd.push_str(&format!("--suffix {} ", s)),
-        SearchMode::Both { prefix, suffix } => cmd.push_str(&format!("--prefix {} --suffix {} ", prefix, suffix)),
-    }
     // Present executor options
     println!("Executor options:");
     println!("  L) Local: slowest but free");
@@ -427,19 +672,32 @@ fn interactive_mode(time: bool) {
     let mut ex_in = String::new();
     io::stdin().read_line(&mut ex_in).unwrap();
     let exec = match ex_in.trim().to_uppercase().as_str() {
-        "C" => Executor::Cpu,
-        "G" => Executor::GcpGpu,
-        "A" => Executor::AwsGpu,
-        _   => Executor::Local,
-    };
-    // Wrap accordingly
-    let final_cmd = match exec {
-        Executor::Cpu    => format!("aws batch submit-job --job-name vanity-search-cpu --job-queue cpu-queue --container-overrides command=[\"sh\",\"-c\",\"{}\"]", cmd),
-        Executor::GcpGpu => format!("gcloud run jobs execute vanity-gpu-job --image gcr.io/myproj/vanity-gpu:latest --args=\"{}\"", cmd),
-        Executor::AwsGpu => format!("aws batch submit-job --job-name vanity-search-gpu --job-queue gpu-queue --container-overrides command=[\"sh\",\"-c\",\"{}\"]", cmd),
-        Executor::Local  => cmd.clone(),
+        "C" => ExecutorKind::Cpu,
+        "G" => ExecutorKind::GcpGpu,
+        "A" => ExecutorKind::AwsGpu,
+        _   => ExecutorKind::Local,
     };
-    println!("➜ {}", final_cmd);
+    let (prefix, suffix) = wizard_patterns(&mode);
+    let patterns = [("--prefix", &prefix), ("--suffix", &suffix)];
+    let args = search_args(gen_mode, words, &patterns, SearchLimits::default(), time);
+    for command in executors.commands(exec, WIZARD_PROGRAM, &args, threads) {
+        println!("➜ {}", command);
+    }
+}
+
+/// The wizard's prefix and suffix, as search options
+fn wizard_patterns(mode: &SearchMode) -> (Option<String>, Option<String>) {
+    match mode {
+        SearchMode::Prefix(p) => (Some(p.clone()), None),
+        SearchMode::Suffix(s) => (None, Some(s.clone())),
+        SearchMode::Both { prefix, suffix } => (Some(prefix.clone()), Some(suffix.clone())),
+    }
+}
+
+/// Average number of candidates needed for the mode, from its matcher's probability
+fn expected_attempts(mode: &SearchMode) -> f64 {
+    let matcher = mode.matcher().expect("wizard patterns are validated by prompt_pattern");
+    1.0 / matcher.probability()
 }
 
 /// Prompt the user to enter a prefix or suffix pattern
@@ -457,49 +715,13 @@ fn prompt_pattern(kind: &str) -> String {
     }
 }
 
-/// Generate a single candidate key (mnemonic or raw) for calibration
-fn generate_candidate(_mode: &SearchMode, words: usize, raw: bool) {
-    if raw {
-        let _ = Keypair::new();
-    } else {
-        let entropy_bytes = if words == 12 { 16 } else { 32 };
-        let mut rng = thread_rng();
-        let mut entropy = vec![0u8; entropy_bytes];
-        rng.fill_bytes(&mut entropy);
-        let m = Mnemonic::from_entropy_in(Language::English, &entropy).unwrap();
-        let seed = m.to_seed("");
-        let _ = Keypair::from_seed(&seed[..32]).unwrap();
-    }
-}
-
-/// Format seconds into a human-readable string
-fn format_duration(secs: f64) -> String {
-    let s = secs.round() as u64;
-    let days = s / 86_400;
-    let hours = (s % 86_400) / 3_600;
-    let mins = (s % 3_600) / 60;
-    let secs = s % 60;
-    let mut parts = Vec::new();
-    if days > 0 {
-        parts.push(format!("{}d", days));
-    }
-    if hours > 0 {
-        parts.push(format!("{}h", hours));
-    }
-    if mins > 0 {
-        parts.push(format!("{}m", mins));
-    }
-    parts.push(format!("{}s", secs));
-    parts.join(" ")
-}
-
 /// Benchmark keypair generation and estimate search times for 5- and 6-character patterns
-fn run_calibration(threads: usize) {
+fn run_calibration(threads: usize, tiers: &[(String, Profile)]) {
     println!("Calibrating key generation speed...");
     let sample = 1_000;
     let start = Instant::now();
     for _ in 0..sample {
-        let _ = Keypair::new();
+        RawKeySource.generate(&mut thread_rng());
     }
     let elapsed = start.elapsed();
     let elapsed_secs = elapsed.as_secs_f64();
@@ -508,225 +730,466 @@ fn run_calibration(threads: usize) {
     println!("Calibration completed in {}", format_duration(elapsed_secs));
     println!("Per-thread rate: {:.2} keys/sec", per_thread);
     println!("Total rate ({} threads): {:.2} keys/sec", threads, total_rate);
-    let space5 = (BASE58_ALPHABET.len() as f64).powi(5);
-    let space6 = (BASE58_ALPHABET.len() as f64).powi(6);
-    let avg5 = space5 / total_rate;
-    let avg6 = space6 / total_rate;
-    let best5 = 1.0 / total_rate;
-    let best6 = 1.0 / total_rate;
-    let worst5 = avg5 * 5.0;
-    let worst6 = avg6 * 5.0;
-    println!("5-character search space: 58^5 ≈ {:.0}", space5);
-    println!("  Best-case: {}", format_duration(best5));
-    println!("  Avg-case: {}", format_duration(avg5));
-    println!("  Very likely (<5× avg): {}", format_duration(worst5));
-    println!("6-character search space: 58^6 ≈ {:.0}", space6);
-    println!("  Best-case: {}", format_duration(best6));
-    println!("  Avg-case: {}", format_duration(avg6));
-    println!("  Very likely (<5× avg): {}", format_duration(worst6));
-}
-// -- Search loop ---------------------------------------------------------------
-/// Runs the brute-force search loop based on the given mode, word-count, key generation mode, and timing option
-fn run_search(mode: SearchMode, words: usize, raw: bool, token: bool, time: bool) {
-    let batch_size = 1_000_000;
-    // Track total and per-batch durations
-    let total_start = Instant::now();
-    let mut batch_count = 0;
-    // Show start notification for wallet searches only
-    if !token {
-        println!("🔍 Starting address search...");
+    // Odds come from the matcher, as in the wizard; a mixed-case pattern needs no case rule at its boundary
+    for pattern in ["Sunny", "Sunset"] {
+        let matcher = PrefixMatcher::new(pattern).expect("calibration patterns are valid Base58");
+        let space = 1.0 / matcher.probability();
+        let avg = space / total_rate;
+        println!("{}-character {}: ≈ {:.0} attempts", pattern.len(), matcher.describe(), space);
+        println!("  Best-case: {}", format_duration(1.0 / total_rate));
+        println!("  Avg-case: {}", format_duration(avg));
+        println!("  Very likely (<5× avg): {}", format_duration(avg * 5.0));
+        print_cost_table(tiers, per_thread, threads, space);
     }
-    loop {
-        batch_count += 1;
-        let batch_start = Instant::now();
-        let found = (0..batch_size).into_par_iter().find_map_any(|_| {
-            if token {
-                // Token address only: generate keypair, check prefix/suffix, return no mnemonic
-                let keypair = Keypair::new();
-                let pubkey = keypair.pubkey().to_string();
-                if matches_mode(&mode, &pubkey) {
-                    Some((String::new(), keypair))
-                } else {
-                    None
-                }
-            } else if raw {
-                // Raw keypair: generate keypair, check, no mnemonic
-                let keypair = Keypair::new();
-                let pubkey = keypair.pubkey().to_string();
-                if matches_mode(&mode, &pubkey) {
-                    Some((String::new(), keypair))
-                } else {
-                    None
-                }
-            } else {
-                // Mnemonic-derived keypair
-                let entropy_bytes = if words == 12 { 16 } else { 32 };
-                let mut rng = thread_rng();
-                let mut entropy = vec![0u8; entropy_bytes];
-                rng.fill_bytes(&mut entropy);
-                let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy).unwrap();
-                let seed = mnemonic.to_seed("");
-                let keypair = Keypair::from_seed(&seed[..32]).unwrap();
-                let pubkey = keypair.pubkey().to_string();
-                if matches_mode(&mode, &pubkey) {
-                    Some((mnemonic.to_string(), keypair))
-                } else {
-                    None
-                }
+}
+
+/// Print the rate, price, and time and cost to 50%/90% odds of a match on each tier
+fn print_cost_table(tiers: &[(String, Profile)], per_thread_rate: f64, threads: usize, avg_tries: f64) {
+    println!("  {:<16} {:>13} {:>7}  {:<26} 90% chance", "Executor", "keys/sec", "$/hr", "50% chance");
+    for (name, profile) in tiers {
+        let Some(model) = profile.cost_model(per_thread_rate, threads) else {
+            println!("  {:<16} {:>13} {:>7}  rate unknown: set keys_per_sec in the profile", name, "?", "-");
+            continue;
+        };
+        let odds = |probability| {
+            let estimate = model.estimate(avg_tries, probability);
+            format!("{} (${:.2})", format_duration(estimate.secs), estimate.usd)
+        };
+        let rate = format!("{:.0}{}", model.keys_per_sec, if profile.keys_per_sec.is_some() { " " } else { "*" });
+        println!("  {:<16} {:>13} {:>7.2}  {:<26} {}", name, rate, model.usd_per_hour, odds(0.5), odds(0.9));
+    }
+    println!("  * this machine's per-thread rate × threads; set keys_per_sec in a profile to use a measured rate");
+    println!("  GPU tiers are listed with their measured rate: --gcp-gpu-rate, --aws-gpu-rate");
+}
+
+/// Candidates timed per stage by `--bench`
+const BENCH_SAMPLES: u32 = 2_000;
+
+/// Print how long each stage of producing and testing a candidate takes on one thread
+fn run_bench(gen_mode: GenerationMode, words: usize, matcher: Arc<dyn Matcher>) -> Result<(), VanityError> {
+    let words_label = if gen_mode == GenerationMode::Mnemonic { format!(", {} words", words) } else { String::new() };
+    eprintln!("Timing {} candidates on one thread...", BENCH_SAMPLES);
+    println!(
+        "Per-candidate breakdown ({:?}{}, match={}):",
+        gen_mode,
+        words_label,
+        matcher.describe()
+    );
+    let timings = candidate_breakdown(gen_mode, words, matcher, BENCH_SAMPLES)?;
+    let total: f64 = timings.iter().map(|t| t.secs_per_candidate).sum();
+    for t in &timings {
+        println!(
+            "  {:<22} {:>10.2} µs  {:>5.1}%",
+            t.stage,
+            t.secs_per_candidate * 1e6,
+            t.secs_per_candidate / total * 100.0
+        );
+    }
+    println!("  {:<22} {:>10.2} µs", "total", total * 1e6);
+    println!("≈ {:.0} keys/sec per thread", 1.0 / total);
+    Ok(())
+}
+// -- Output ----------------------------------------------------------------------
+/// Writes search notifications in the selected format
+struct Reporter {
+    format: OutputFormat,
+    /// Token searches print no start/progress notifications in text mode
+    token: bool,
+    /// Result sinks that could not store the match
+    sink_failures: Cell<usize>,
+}
+
+impl Reporter {
+    /// Handle a notification from `VanitySearch::run`
+    fn event(&self, event: SearchEvent<'_>) {
+        match event {
+            SearchEvent::Started { config, resumed_attempts, resumed_elapsed_secs } => {
+                self.start(config, resumed_attempts, resumed_elapsed_secs)
             }
-        });
-        if let Some((mnemonic, keypair)) = found {
-            let pubkey = keypair.pubkey().to_string();
-            let private_key = bs58::encode(&keypair.to_bytes()).into_string();
-            let total_duration = total_start.elapsed();
-            if token {
-                println!("Token Address: {}", pubkey);
-                if time {
-                    println!("⏱ Total run time: {}", format_duration(total_duration.as_secs_f64()));
-                }
-                println!("⚠️  Record your token address now, then delete this message for safety.");
-            } else {
-                if !raw {
-                    println!("Mnemonic: {}", mnemonic);
-                }
-                println!("Public Address: {}", pubkey);
-                println!("Base58 Private Key: {}", private_key);
-                if time {
-                    println!("⏱ Total run time: {}", format_duration(total_duration.as_secs_f64()));
-                }
-                println!("⚠️  Record your address and private key now, then delete for safety.");
+            SearchEvent::Progress { progress } => self.progress(progress),
+            SearchEvent::Stopped { reason, progress } => self.stopped(reason, progress),
+            SearchEvent::CheckpointFailed { checkpoint, error } => {
+                eprintln!("Warning: failed to write state file {}: {}", checkpoint.path.display(), error)
             }
-            return;
+            SearchEvent::SinkFailed { sink, error } => {
+                self.sink_failures.set(self.sink_failures.get() + 1);
+                eprintln!("Error: could not store the result in {}: {}", sink, error)
+            }
+            SearchEvent::WorkerJoined { worker, threads } => match self.format {
+                OutputFormat::Jsonl => Event::WorkerJoined { worker, threads }.emit(),
+                _ => eprintln!("🖥️  Worker {} joined ({} threads)", worker, threads),
+            },
+            SearchEvent::WorkerLeft { worker, reason } => match self.format {
+                OutputFormat::Jsonl => Event::WorkerLeft { worker, reason }.emit(),
+                _ => eprintln!("🖥️  Worker {} left: {}", worker, reason),
+            },
         }
-        let batch_duration = batch_start.elapsed();
-        let total_duration = total_start.elapsed();
-        // Batch progress notification for wallet searches only
-        if !token {
-            println!(
-                "❌ Batch #{}: no match (batch: {}, total: {})",
-                batch_count,
-                format_duration(batch_duration.as_secs_f64()),
-                format_duration(total_duration.as_secs_f64()),
-            );
+    }
+
+    fn start(&self, config: &SearchConfig, resumed_attempts: u64, resumed_elapsed_secs: f64) {
+        match self.format {
+            OutputFormat::Jsonl => Event::Start { config, resumed_attempts, resumed_elapsed_secs }.emit(),
+            // Show start notification for wallet searches only
+            _ if self.token => {}
+            _ if resumed_attempts > 0 => eprintln!(
+                "🔍 Resuming address search after {} attempts ({})...",
+                resumed_attempts,
+                format_duration(resumed_elapsed_secs)
+            ),
+            _ => eprintln!("🔍 Starting address search..."),
         }
     }
-}
 
-/// Checks whether a given public-key string matches the prefix/suffix mode and case rules
-fn matches_mode(mode: &SearchMode, pubkey: &str) -> bool {
-    match mode {
-        SearchMode::Prefix(p) => {
-            if !pubkey.starts_with(p) {
-                return false;
-            }
-            // Next character rule after prefix
-            match pubkey.chars().nth(p.len()) {
-                Some(ch) if p.chars().all(|c| c.is_ascii_uppercase()) => ch.is_ascii_lowercase(),
-                Some(ch) if p.chars().all(|c| c.is_ascii_lowercase()) => ch.is_ascii_uppercase(),
-                Some(ch) if p.chars().all(|c| c.is_ascii_digit())    => ch.is_ascii_alphabetic(),
-                Some(_)                                                => true,
-                None                                                   => false,
+    fn progress(&self, progress: &Progress) {
+        match self.format {
+            OutputFormat::Jsonl => Event::Progress(progress).emit(),
+            // Periodic progress notification for wallet searches only
+            _ if self.token => {}
+            _ => {
+                let eta = match progress.eta_secs {
+                    Some(secs) => format_duration(secs),
+                    None => "past average, keep going".to_string(),
+                };
+                eprintln!(
+                    "⏳ No match yet (elapsed: {}, attempts: {}, rate: {:.0} keys/sec, ETA: {})",
+                    format_duration(progress.elapsed_secs),
+                    progress.attempts,
+                    progress.rate,
+                    eta,
+                );
             }
         }
-        SearchMode::Suffix(s) => {
-            if !pubkey.ends_with(s) {
-                return false;
+    }
+
+    /// Fail (exit code 4) if any result sink could not store the match
+    fn finish(&self) -> Result<(), VanityError> {
+        match self.sink_failures.get() {
+            0 => Ok(()),
+            n => Err(VanityError::io(
+                "cannot store the result",
+                std::io::Error::other(format!("{} result sink(s) failed", n)),
+            )),
+        }
+    }
+
+    /// Print attempts, rate, elapsed time and the probability reached so far
+    fn stopped(&self, reason: &str, progress: &Progress) {
+        match self.format {
+            OutputFormat::Json | OutputFormat::Jsonl => Event::Stopped { reason, progress }.emit(),
+            OutputFormat::Text => {
+                eprintln!("\n🛑 Search stopped: {}", reason);
+                eprintln!("  Attempts: {} ({} this session)", progress.attempts, progress.session_attempts);
+                eprintln!("  Rate: {:.2} keys/sec", progress.rate);
+                eprintln!("  Elapsed: {}", format_duration(progress.elapsed_secs));
+                eprintln!("  Probability of a match by now: {:.2}%", progress.probability * 100.0);
             }
-            // Previous character rule before suffix
-            let idx = pubkey.len().saturating_sub(s.len()).saturating_sub(1);
-            match pubkey.chars().nth(idx) {
-                Some(ch) if s.chars().all(|c| c.is_ascii_uppercase()) => ch.is_ascii_lowercase(),
-                Some(ch) if s.chars().all(|c| c.is_ascii_lowercase()) => ch.is_ascii_uppercase(),
-                Some(ch) if s.chars().all(|c| c.is_ascii_digit())    => ch.is_ascii_alphabetic(),
-                Some(_)                                                => true,
-                None                                                   => false,
+        }
+    }
+}
+
+/// Hourly list-price ballparks for the built-in tiers' cost estimates (a profile can set its own)
+const CPU_USD_PER_HOUR: f64 = 0.10;
+const GCP_GPU_USD_PER_HOUR: f64 = 3.67;
+const AWS_GPU_USD_PER_HOUR: f64 = 20.0;
+
+/// Read --config, or vanity.toml in the working directory if there is one
+fn load_profiles(config_file: Option<PathBuf>) -> Result<ProfileConfig, VanityError> {
+    match config_file {
+        Some(path) => ProfileConfig::load(&path),
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => ProfileConfig::load(Path::new(DEFAULT_CONFIG_FILE)),
+        None => Ok(ProfileConfig::default()),
+    }
+}
+
+/// Job names, queues and images for the built-in --executor tiers
+struct ExecutorFlags {
+    cpu_job: String,
+    cpu_queue: String,
+    gcp_gpu_job: String,
+    gcp_gpu_image: Option<String>,
+    /// Measured keys/sec of the GPU tiers; this machine's CPU rate says nothing about them
+    gcp_gpu_rate: Option<f64>,
+    aws_gpu_job: String,
+    aws_gpu_queue: String,
+    aws_gpu_rate: Option<f64>,
+    ssh_hosts: Vec<SshHost>,
+    ssh_upload: bool,
+    /// Split --threads between the SSH hosts rather than letting each use all its cores
+    ssh_threads: bool,
+    container_image: Option<String>,
+    container_limits: ResourceLimits,
+}
+
+impl ExecutorFlags {
+    /// The profile an --executor tier stands for (None: search in this process)
+    fn profile(&self, kind: ExecutorKind) -> Option<Profile> {
+        match kind {
+            ExecutorKind::Local => None,
+            ExecutorKind::Process => Some(Profile::new(ProfileKind::Process)),
+            ExecutorKind::Cpu => Some(Profile {
+                job: Some(self.cpu_job.clone()),
+                queue: Some(self.cpu_queue.clone()),
+                usd_per_hour: Some(CPU_USD_PER_HOUR),
+                ..Profile::new(ProfileKind::AwsBatch)
+            }),
+            ExecutorKind::GcpGpu => Some(Profile {
+                job: Some(self.gcp_gpu_job.clone()),
+                image: self.gcp_gpu_image.clone(),
+                usd_per_hour: Some(GCP_GPU_USD_PER_HOUR),
+                keys_per_sec: self.gcp_gpu_rate,
+                ..Profile::new(ProfileKind::CloudRun)
+            }),
+            ExecutorKind::AwsGpu => Some(Profile {
+                job: Some(self.aws_gpu_job.clone()),
+                queue: Some(self.aws_gpu_queue.clone()),
+                usd_per_hour: Some(AWS_GPU_USD_PER_HOUR),
+                keys_per_sec: self.aws_gpu_rate,
+                ..Profile::new(ProfileKind::AwsBatch)
+            }),
+            ExecutorKind::Ssh => {
+                let mut profile = Profile {
+                    hosts: self.ssh_hosts.iter().map(SshHost::to_string).collect(),
+                    upload: self.ssh_upload,
+                    ..Profile::new(ProfileKind::Ssh)
+                };
+                if !self.ssh_threads {
+                    profile.command = vec!["{search}".to_string()];
+                }
+                Some(profile)
             }
+            ExecutorKind::Docker | ExecutorKind::Podman => Some(Profile {
+                image: self.container_image.clone(),
+                limits: self.container_limits,
+                ..Profile::new(if kind == ExecutorKind::Docker { ProfileKind::Docker } else { ProfileKind::Podman })
+            }),
         }
-        SearchMode::Both { prefix, suffix } => {
-            // Combined prefix and suffix check
-            // Prefix
-            if !pubkey.starts_with(prefix) {
-                return false;
+    }
+
+    /// Executors compared in cost estimates: this machine, the remote tiers, then the config file's profiles.
+    /// The GPU tiers are only listed once their rate is given.
+    fn cost_tiers(&self, profiles: &ProfileConfig) -> Vec<(String, Profile)> {
+        let mut tiers = vec![("local".to_string(), Profile::new(ProfileKind::Process))];
+        for kind in [ExecutorKind::Cpu, ExecutorKind::GcpGpu, ExecutorKind::AwsGpu] {
+            let profile = self.profile(kind).expect("remote tiers have profiles");
+            if kind != ExecutorKind::Cpu && profile.keys_per_sec.is_none() {
+                continue;
             }
-            let ok_prefix = match pubkey.chars().nth(prefix.len()) {
-                Some(ch) if prefix.chars().all(|c| c.is_ascii_uppercase()) => ch.is_ascii_lowercase(),
-                Some(ch) if prefix.chars().all(|c| c.is_ascii_lowercase()) => ch.is_ascii_uppercase(),
-                Some(ch) if prefix.chars().all(|c| c.is_ascii_digit())    => ch.is_ascii_alphabetic(),
-                Some(_)                                                    => true,
-                None                                                       => false,
-            };
-            if !ok_prefix {
-                return false;
+            let name = kind.to_possible_value().expect("executors are named").get_name().to_string();
+            tiers.push((name, profile));
+        }
+        tiers.extend(profiles.profiles.iter().map(|(name, profile)| (format!("profile {}", name), profile.clone())));
+        tiers
+    }
+
+    /// Shell command lines that start the search on `kind`. Each argument stays a separate argv entry
+    /// (container command, gcloud list) and is quoted for a POSIX shell, so patterns never reach a shell
+    /// unescaped.
+    fn commands(&self, kind: ExecutorKind, program: &str, args: &[String], threads: usize) -> Vec<String> {
+        let plan = match self.profile(kind) {
+            Some(profile) => profile
+                .executor(program, SystemRunner)
+                .expect("built-in profiles are valid")
+                .plan(&profile.job(args, threads)),
+            None => {
+                let mut argv = vec![program.to_string(), "--threads".to_string(), threads.to_string()];
+                argv.extend_from_slice(args);
+                vec![argv]
             }
-            // Suffix
-            if !pubkey.ends_with(suffix) {
-                return false;
+        };
+        plan.iter().map(|argv| shell_join(argv)).collect()
+    }
+}
+
+/// Options that reproduce a search on another machine (thread count excluded)
+fn search_args(
+    gen_mode: GenerationMode,
+    words: usize,
+    patterns: &[(&str, &Option<String>)],
+    limits: SearchLimits,
+    time: bool,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    match gen_mode {
+        GenerationMode::Raw => args.push("--raw".to_string()),
+        GenerationMode::Token => args.push("--token".to_string()),
+        GenerationMode::Mnemonic => args.extend(["--words".to_string(), words.to_string()]),
+    }
+    for (flag, value) in patterns {
+        if let Some(value) = value {
+            args.extend([flag.to_string(), value.clone()]);
+        }
+    }
+    if let Some(limit) = limits.max_time {
+        args.extend(["--max-time".to_string(), format!("{}s", limit.as_secs())]);
+    }
+    if let Some(limit) = limits.max_attempts {
+        args.extend(["--max-attempts".to_string(), limit.to_string()]);
+    }
+    if time {
+        args.push("--time".to_string());
+    }
+    args
+}
+
+/// Submit `job`, report status changes every `poll` until it ends, then print its output.
+/// Ctrl-C cancels the job.
+fn follow_job(executor: &mut dyn Executor, job: &JobSpec, poll: Duration) -> Result<(), VanityError> {
+    let handle = executor.submit(job)?;
+    eprintln!("⭐ Submitted job {} to the {} executor", handle.id, executor.name());
+    let stop = install_stop_handler();
+    let mut last = None;
+    let status = loop {
+        if stop.load(Ordering::SeqCst) {
+            executor.cancel(&handle)?;
+            eprintln!("Cancelled job {}", handle.id);
+            return Err(VanityError::Interrupted);
+        }
+        let status = executor.status(&handle)?;
+        if last.as_ref() != Some(&status) {
+            eprintln!("Job {}: {}", handle.id, status);
+        }
+        if let Some(progress) = executor.progress(&handle)? {
+            eprintln!(
+                "⏳ No match yet ({} attempts, {:.0} keys/sec, {} reporting)",
+                progress.attempts, progress.rate, progress.reporting
+            );
+        }
+        if status.is_finished() {
+            break status;
+        }
+        last = Some(status);
+        // Sleep in short steps so Ctrl-C is handled promptly
+        let wake = Instant::now() + poll;
+        while Instant::now() < wake && !stop.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    };
+    // The job's own output carries the result (or its summary)
+    print!("{}", executor.logs(&handle)?);
+    match status {
+        JobStatus::Succeeded => {
+            if let Some(path) = executor.result_file(&handle).filter(|path| path.exists()) {
+                eprintln!("💾 Result collected in {}", path.display());
             }
-            let idx = pubkey.len().saturating_sub(suffix.len()).saturating_sub(1);
-            let ok_suffix = match pubkey.chars().nth(idx) {
-                Some(ch) if suffix.chars().all(|c| c.is_ascii_uppercase()) => ch.is_ascii_lowercase(),
-                Some(ch) if suffix.chars().all(|c| c.is_ascii_lowercase()) => ch.is_ascii_uppercase(),
-                Some(ch) if suffix.chars().all(|c| c.is_ascii_digit())    => ch.is_ascii_alphabetic(),
-                Some(_)                                                    => true,
-                None                                                       => false,
-            };
-            ok_suffix
+            Ok(())
         }
+        JobStatus::Cancelled => Err(VanityError::Interrupted),
+        status => Err(VanityError::io(format!("job {} did not succeed", handle.id), std::io::Error::other(status.to_string()))),
     }
 }
 
+/// Install SIGINT/SIGTERM handlers that raise the returned stop flag; a second signal exits immediately
+fn install_stop_handler() -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&stop);
+    let res = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        eprintln!("\nStopping search (press Ctrl-C again to abort immediately)...");
+    });
+    if let Err(e) = res {
+        eprintln!("Warning: failed to install signal handler: {}", e);
+    }
+    stop
+}
+
 fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<(), VanityError> {
     // Parse CLI and destructure to avoid partial moves
-    let Args { show_alphabet, interactive, calibrate, time, prefix, suffix, raw, token, words, threads: threads_opt, executor, cpu_job, cpu_queue, gcp_gpu_job, gcp_gpu_image, aws_gpu_job, aws_gpu_queue } = Args::parse();
+    let Args { show_alphabet, interactive, calibrate, bench, time, prefix, suffix, raw, token, words, threads: threads_opt, nice, pin_cpus, executor, profile, config: config_file, dry_run, cpu_job, cpu_queue, gcp_gpu_job, gcp_gpu_image, gcp_gpu_rate, aws_gpu_job, aws_gpu_queue, aws_gpu_rate, ssh_hosts, ssh_upload, container_image, container_cpus, container_memory, state_file, resume, checkpoint_interval, progress_interval, max_time, max_attempts, format, outfile, sinks, decrypt, rng_seed, regex, command } = Args::parse();
+    let executors = ExecutorFlags {
+        cpu_job,
+        cpu_queue,
+        gcp_gpu_job,
+        gcp_gpu_image,
+        gcp_gpu_rate,
+        aws_gpu_job,
+        aws_gpu_queue,
+        aws_gpu_rate,
+        ssh_hosts,
+        ssh_upload,
+        ssh_threads: threads_opt.is_some(),
+        container_image,
+        container_limits: ResourceLimits { cpus: container_cpus, memory_mib: container_memory, gpus: None },
+    };
     // If requested, just show the Base58 alphabet and exit
     if show_alphabet {
         println!("Allowed Base58 alphabet: {}", BASE58_ALPHABET);
-        return;
+        return Ok(());
+    }
+    if let Some(path) = decrypt {
+        let result = decrypt_result_file(&path, &passphrase()?)?;
+        println!("{}", serde_json::to_string_pretty(&result).expect("result serializes"));
+        return Ok(());
     }
     // If interactive mode, run the wizard and exit
     if interactive {
-        interactive_mode(time);
-        return;
+        interactive_mode(time, &executors, &executors.cost_tiers(&load_profiles(config_file)?));
+        return Ok(());
     }
     if calibrate {
         let threads = threads_opt.unwrap_or_else(num_cpus::get);
-        run_calibration(threads);
-        return;
+        run_calibration(threads, &executors.cost_tiers(&load_profiles(config_file)?));
+        return Ok(());
     }
-    // Determine search mode: prefix, suffix, or both
-    let mode = match (prefix, suffix) {
-        (Some(p), Some(s)) => SearchMode::Both { prefix: p, suffix: s },
-        (Some(p), None)    => SearchMode::Prefix(p),
-        (None, Some(s))    => SearchMode::Suffix(s),
-        _ => {
-            eprintln!("Error: must specify --prefix, --suffix, or both (or use --interactive)");
-            return;
+    // API jobs bring their own patterns, generation mode and limits
+    if let Some(Command::Serve { listen, max_jobs, store }) = &command {
+        if *max_jobs == 0 {
+            return Err(VanityError::InvalidInput("--max-jobs must be at least 1".to_string()));
         }
-    };
-    // Validate patterns against Base58 alphabet
-    let patterns = match &mode {
-        SearchMode::Prefix(p)       => vec![p],
-        SearchMode::Suffix(s)       => vec![s],
-        SearchMode::Both { prefix, suffix } => vec![prefix, suffix],
-    };
-    for pat in patterns {
-        for c in pat.chars() {
-            if !BASE58_ALPHABET.contains(c) {
-                eprintln!("Error: Invalid character '{}' in pattern", c);
-                println!("Allowed Base58 alphabet: {}", BASE58_ALPHABET);
-                return;
+        // Concurrent jobs share the CPUs unless told otherwise
+        let threads = threads_opt.unwrap_or_else(|| (num_cpus::get() / max_jobs).max(1));
+        let mut service = SearchService::new(threads)
+            .max_jobs(*max_jobs)
+            .progress_interval(Duration::from_secs(progress_interval))
+            .placement(ThreadPlacement { nice, cpus: pin_cpus.unwrap_or_default() });
+        if let Some(dir) = store {
+            service = service.store(dir, Duration::from_secs(checkpoint_interval))?;
+        }
+        return run_server(listen, service);
+    }
+    // Workers get their pattern and generation mode from the coordinator
+    if let Some(Command::Worker { connect, name }) = &command {
+        let mut search = VanitySearch::new()
+            .limits(SearchLimits { max_time, max_attempts })
+            .progress_interval(Duration::from_secs(progress_interval))
+            .placement(ThreadPlacement { nice, cpus: pin_cpus.unwrap_or_default() });
+        if let Some(threads) = threads_opt {
+            search = search.threads(threads);
+        }
+        eprintln!("Joining coordinator at {} with {} threads...", connect, search.thread_count());
+        let reporter = Reporter { format, token: false, sink_failures: Cell::new(0) };
+        let outcome = search
+            .stop_flag(install_stop_handler())
+            .join_cluster(connect.as_str(), name.clone(), cluster_token(), |event| reporter.event(event))?;
+        match outcome {
+            WorkerOutcome::Found { public_address } => {
+                eprintln!("⚡ Found {}; the keys were sent to the coordinator", public_address)
             }
+            WorkerOutcome::Stopped { reason } => eprintln!("Coordinator ended the search: {}", reason),
         }
+        return Ok(());
+    }
+    // Benchmarks need some pattern to match against; any short one is representative
+    let prefix = if bench && prefix.is_none() && suffix.is_none() && regex.is_none() {
+        Some("Sun".to_string())
+    } else {
+        prefix
+    };
+    if prefix.is_none() && suffix.is_none() && regex.is_none() {
+        return Err(VanityError::InvalidInput(
+            "must specify --prefix, --suffix, --regex, or a combination (or use --interactive)".to_string(),
+        ));
     }
     // Determine thread count (use all logical CPUs if not specified)
     let threads = threads_opt.unwrap_or_else(num_cpus::get);
-
-
-    // Initialize the Rayon thread pool
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(threads)
-        .build_global()
-        .expect("Failed to build thread pool");
     // Determine generation mode for non-interactive
     let gen_mode = if token {
         GenerationMode::Token
@@ -735,55 +1198,205 @@ fn main() {
     } else {
         GenerationMode::Mnemonic
     };
-    // If using a remote executor, build and print the submission command, then exit
-    if executor != Executor::Local {
-        // Build the inner binary invocation
-        let mut inner = format!("./target/release/solana-vanity-seed --threads {} ", threads);
-        if time {
-            inner.push_str("--time ");
-        }
-        match gen_mode {
-            GenerationMode::Raw => inner.push_str("--raw "),
-            GenerationMode::Token => inner.push_str("--token "),
-            GenerationMode::Mnemonic => inner.push_str(&format!("--words {} ", words)),
-        }
-        match &mode {
-            SearchMode::Prefix(p) => inner.push_str(&format!("--prefix {} ", p)),
-            SearchMode::Suffix(s) => inner.push_str(&format!("--suffix {} ", s)),
-            SearchMode::Both { prefix, suffix } => inner.push_str(&format!("--prefix {} --suffix {} ", prefix, suffix)),
-        }
-        // Wrap in executor template according to selected tier
-        let submission = match executor {
-            Executor::Cpu => {
-                // Remote CPU cluster (AWS Batch)
-                format!(
-                    "aws batch submit-job --job-name {job_name} --job-queue {queue} --job-definition {job_def} --container-overrides command=[\"sh\",\"-c\",\"{cmd}\"]",
-                    job_name = cpu_job,
-                    queue = cpu_queue,
-                    job_def = cpu_job,
-                    cmd = inner
-                )
-            }
-            Executor::GcpGpu => {
-                // GCP A100 GPU job
-                format!("gcloud run jobs execute {} --image {} --args=\"{}\"", gcp_gpu_job, gcp_gpu_image, inner)
-            }
-            Executor::AwsGpu => {
-                // AWS GPU job (AWS Batch)
-                format!(
-                    "aws batch submit-job --job-name {job_name} --job-queue {queue} --job-definition {job_def} --container-overrides command=[\"sh\",\"-c\",\"{cmd}\"]",
-                    job_name = aws_gpu_job,
-                    queue = aws_gpu_queue,
-                    job_def = aws_gpu_job,
-                    cmd = inner
-                )
+    let mut search = VanitySearch::new()
+        .generation(gen_mode)
+        .words(words)
+        .threads(threads)
+        .limits(SearchLimits { max_time, max_attempts })
+        .progress_interval(Duration::from_secs(progress_interval))
+        .placement(ThreadPlacement { nice, cpus: pin_cpus.unwrap_or_default() });
+    if let Some(p) = prefix {
+        search = search.prefix(p);
+    }
+    if let Some(s) = suffix {
+        search = search.suffix(s);
+    }
+    if let Some(r) = &regex {
+        search = search.regex(r.clone());
+    }
+    // Options only a local search honors; a job handed to another executor would silently drop them
+    let local_options = [
+        ("--state-file", state_file.is_some()),
+        ("--outfile", outfile.is_some()),
+        ("--sink", !sinks.is_empty()),
+        ("--rng-seed", rng_seed.is_some()),
+    ];
+    if let Some(seed) = rng_seed {
+        eprintln!("⚠️  WARNING: --rng-seed makes every key predictable. Never use these keys for real funds.");
+        search = search.rng_seed(seed);
+    }
+    // --outfile is shorthand for a keypair sink; the terminal always gets the result last and only
+    // shows secrets no other sink stored
+    if let Some(path) = outfile {
+        search = search.sink(KeypairFileSink { path });
+    }
+    for spec in &sinks {
+        search = search.sink(parse_sink(spec)?);
+    }
+    search = search.sink(TerminalSink { format, time });
+    // Validates the patterns against the Base58 alphabet and compiles the regex
+    let config = search.config()?;
+    let matcher = search.build_matcher()?;
+    if bench {
+        return run_bench(gen_mode, words, matcher);
+    }
+    if let Some(Command::Coordinator { listen }) = &command {
+        if state_file.is_some() {
+            return Err(VanityError::InvalidInput("--state-file is not supported by the coordinator".to_string()));
+        }
+        let listener = TcpListener::bind(listen).map_err(|e| VanityError::io(format!("cannot listen on {}", listen), e))?;
+        let addr = listener.local_addr().map_err(|e| VanityError::io(format!("cannot listen on {}", listen), e))?;
+        eprintln!("Coordinating search on {}: match={}, gen_mode={:?}, words={}...", addr, matcher.describe(), gen_mode, words);
+        let reporter = Reporter { format, token, sink_failures: Cell::new(0) };
+        search
+            .stop_flag(install_stop_handler())
+            .coordinate(listener, cluster_token(), |event| reporter.event(event))?;
+        return reporter.finish();
+    }
+    // Options that reproduce this search on another machine
+    let patterns = [("--prefix", &config.prefix), ("--suffix", &config.suffix), ("--regex", &config.regex)];
+    let args = search_args(gen_mode, words, &patterns, SearchLimits { max_time, max_attempts }, time);
+    let profile = match (profile, executor) {
+        (Some(name), _) => Some(load_profiles(config_file)?.profile(&name)?.clone()),
+        (None, kind) => executors.profile(kind),
+    };
+    if let Some(Command::RenderJob { target, image, parallelism, completions }) = command {
+        let profile = profile.unwrap_or_else(|| Profile::new(ProfileKind::Process));
+        let image = image.or_else(|| profile.image.clone()).ok_or_else(|| {
+            VanityError::InvalidInput("render-job needs a container image (--image or the profile's image)".to_string())
+        })?;
+        let fan_out = FanOut { parallelism, completions: completions.unwrap_or(parallelism) };
+        let manifest = render_job(target.into(), &profile.job(&args, threads), &image, &profile.limits, fan_out)?;
+        println!("{}", serde_json::to_string_pretty(&manifest).expect("manifests serialize"));
+        return Ok(());
+    }
+    // Hand the search to another executor and follow the job until it ends
+    if let Some(profile) = profile {
+        if let Some((flag, _)) = local_options.iter().find(|(_, given)| *given) {
+            return Err(VanityError::InvalidInput(format!(
+                "{} only applies to local searches, not to --executor or --profile jobs",
+                flag
+            )));
+        }
+        let program = std::env::current_exe().map_err(|e| VanityError::io("cannot locate this program", e))?;
+        let mut runner = profile.executor(program, SystemRunner)?;
+        let job = profile.job(&args, threads);
+        if dry_run {
+            for argv in runner.plan(&job) {
+                println!("{}", shell_join(&argv));
             }
-            Executor::Local => inner.clone(),
-        };
-        println!("\n⭐ Submit this command to {} executor:\n➜ {}", format!("{:?}", executor).to_lowercase(), submission);
-        return;
+            return Ok(());
+        }
+        return follow_job(runner.as_mut(), &job, Duration::from_secs(progress_interval));
+    }
+    // Load or create the state file used for checkpointing
+    let mut completed = false;
+    if let Some(path) = state_file {
+        if resume && !path.exists() {
+            eprintln!("No state file at {}; starting a new search", path.display());
+        }
+        let checkpoint = Checkpoint::open(path, Duration::from_secs(checkpoint_interval), resume, config.clone())?;
+        // The search reports the recorded result through the sinks instead of running again
+        completed = !checkpoint.state.results.is_empty();
+        if completed {
+            eprintln!("Search already completed; reporting the result recorded in {}", checkpoint.path.display());
+        }
+        search = search.checkpoint(checkpoint);
     }
     // Local execution: start search loop
-    eprintln!("Starting search: {} threads, mode={:?}, gen_mode={:?}, words={}...", threads, mode, gen_mode, words);
-    run_search(mode, words, raw, token, time);
+    if !completed {
+        eprintln!("Starting search: {} threads, match={}, gen_mode={:?}, words={}...", threads, matcher.describe(), gen_mode, words);
+    }
+    let reporter = Reporter { format, token, sink_failures: Cell::new(0) };
+    search
+        .stop_flag(install_stop_handler())
+        .run(|event| reporter.event(event))?;
+    reporter.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_count_must_be_12_or_24() {
+        assert_eq!(parse_word_count("12"), Ok(12));
+        assert_eq!(parse_word_count("24"), Ok(24));
+        for bad in ["0", "13", "18", "-12", "twelve", ""] {
+            assert!(parse_word_count(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn durations_accept_units() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("45m"), Ok(Duration::from_secs(2_700)));
+        assert_eq!(parse_duration("6h"), Ok(Duration::from_secs(21_600)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert_eq!(parse_duration(" 1h30m "), Ok(Duration::from_secs(5_400)));
+        for bad in ["", "h", "5x", "1h30", "0", "0s", "-5m"] {
+            assert!(parse_duration(bad).is_err(), "{:?}", bad);
+        }
+        for huge in ["99999999999999999d", "18446744073709551615s1s"] {
+            assert!(parse_duration(huge).unwrap_err().contains("too large"), "{:?}", huge);
+        }
+    }
+
+    #[test]
+    fn executor_commands_quote_every_argument() {
+        let executors = ExecutorFlags {
+            cpu_job: "nightly".to_string(),
+            cpu_queue: "spot queue".to_string(),
+            gcp_gpu_job: "gpu".to_string(),
+            gcp_gpu_image: None,
+            gcp_gpu_rate: None,
+            aws_gpu_job: "vanity-search-gpu".to_string(),
+            aws_gpu_queue: "gpu-queue".to_string(),
+            aws_gpu_rate: None,
+            ssh_hosts: Vec::new(),
+            ssh_upload: false,
+            ssh_threads: false,
+            container_image: None,
+            container_limits: ResourceLimits::default(),
+        };
+        let regex = Some("^a'b$(rm -rf ~),\"".to_string());
+        let args = search_args(GenerationMode::Raw, 0, &[("--regex", &regex)], SearchLimits::default(), false);
+        let local = executors.commands(ExecutorKind::Local, "vanity", &args, 2);
+        assert_eq!(local, [r#"vanity --threads 2 --raw --regex '^a'\''b$(rm -rf ~),"'"#]);
+        let batch = executors.commands(ExecutorKind::Cpu, "vanity", &args, 2);
+        assert_eq!(
+            batch,
+            [r#"aws batch submit-job --job-name nightly --job-queue 'spot queue' --job-definition nightly --container-overrides '{"command":["--threads","2","--raw","--regex","^a'\''b$(rm -rf ~),\""]}' --output json"#]
+        );
+        let cloud_run = executors.commands(ExecutorKind::GcpGpu, "vanity", &args, 2);
+        assert_eq!(
+            cloud_run,
+            [r#"gcloud run jobs execute gpu '--args=^|^--threads|2|--raw|--regex|^a'\''b$(rm -rf ~),"' --async --format=json"#]
+        );
+    }
+
+    #[test]
+    fn cpu_lists_accept_ranges() {
+        assert_eq!(parse_cpu_list("3"), Ok(vec![3]));
+        assert_eq!(parse_cpu_list("0-3,6"), Ok(vec![0, 1, 2, 3, 6]));
+        assert_eq!(parse_cpu_list(" 2 - 3 , 1 "), Ok(vec![2, 3, 1]));
+        for bad in ["", "a", "3-1", "1,", "-2", "1-"] {
+            assert!(parse_cpu_list(bad).is_err(), "{:?}", bad);
+        }
+        for huge in ["0-100000000000", "1024", "2,1000-1024"] {
+            assert!(parse_cpu_list(huge).unwrap_err().contains("out of range"), "{:?}", huge);
+        }
+        assert_eq!(parse_cpu_list("1023").unwrap(), [1023]);
+    }
+
+    #[test]
+    fn sink_specs_are_parsed() {
+        assert_eq!(parse_sink("keypair:k.json").unwrap().name(), "keypair file k.json");
+        assert_eq!(parse_sink("jsonl:r.jsonl").unwrap().name(), "JSON lines file r.jsonl");
+        assert_eq!(parse_sink("webhook:http://localhost:8080/hit").unwrap().name(), "webhook http://localhost:8080/hit");
+        for bad in ["keypair", "keypair:", "ftp:x", "webhook:http://example.com/"] {
+            assert!(parse_sink(bad).is_err(), "{:?}", bad);
+        }
+    }
 }
@@ -0,0 +1,386 @@
+use crate::error::VanityError;
+use crate::search::SearchMode;
+use crate::{validate_pattern, BASE58_ALPHABET};
+use regex::Regex;
+
+/// Decides whether a Base58 address is a hit
+pub trait Matcher: Send + Sync {
+    /// Whether `address` matches
+    fn is_match(&self, address: &str) -> bool;
+
+    /// Probability that a uniformly random address matches; the estimator expects 1/probability attempts
+    fn probability(&self) -> f64;
+
+    /// Short human-readable description, e.g. `prefix "Sol"`
+    fn describe(&self) -> String;
+}
+
+/// Case rule for the character next to a pattern, so the pattern stands out: an all-uppercase pattern
+/// must border a lowercase letter, all-lowercase an uppercase letter, and all-digit any letter
+fn boundary_ok(pattern: &str, neighbor: Option<char>) -> bool {
+    match neighbor {
+        Some(ch) if pattern.chars().all(|c| c.is_ascii_uppercase()) => ch.is_ascii_lowercase(),
+        Some(ch) if pattern.chars().all(|c| c.is_ascii_lowercase()) => ch.is_ascii_uppercase(),
+        Some(ch) if pattern.chars().all(|c| c.is_ascii_digit())    => ch.is_ascii_alphabetic(),
+        Some(_)                                                      => true,
+        None                                                         => false,
+    }
+}
+
+/// Share of Base58 characters that satisfy `boundary_ok` next to `pattern`
+fn boundary_probability(pattern: &str) -> f64 {
+    let ok = BASE58_ALPHABET.chars().filter(|&c| boundary_ok(pattern, Some(c))).count();
+    ok as f64 / BASE58_ALPHABET.len() as f64
+}
+
+/// Probability of `n` fixed Base58 characters
+fn literal_probability(n: usize) -> f64 {
+    (BASE58_ALPHABET.len() as f64).powi(-(n as i32))
+}
+
+/// Address starts with the pattern (plus the case rule for the next character)
+#[derive(Debug, Clone)]
+pub struct PrefixMatcher {
+    pattern: String,
+}
+
+impl PrefixMatcher {
+    pub fn new(pattern: impl Into<String>) -> Result<Self, VanityError> {
+        let pattern = pattern.into();
+        validate_pattern(&pattern)?;
+        Ok(PrefixMatcher { pattern })
+    }
+}
+
+impl Matcher for PrefixMatcher {
+    fn is_match(&self, address: &str) -> bool {
+        match address.strip_prefix(self.pattern.as_str()) {
+            Some(rest) => boundary_ok(&self.pattern, rest.chars().next()),
+            None => false,
+        }
+    }
+
+    fn probability(&self) -> f64 {
+        literal_probability(self.pattern.chars().count()) * boundary_probability(&self.pattern)
+    }
+
+    fn describe(&self) -> String {
+        format!("prefix {:?}", self.pattern)
+    }
+}
+
+/// Address ends with the pattern (plus the case rule for the previous character)
+#[derive(Debug, Clone)]
+pub struct SuffixMatcher {
+    pattern: String,
+}
+
+impl SuffixMatcher {
+    pub fn new(pattern: impl Into<String>) -> Result<Self, VanityError> {
+        let pattern = pattern.into();
+        validate_pattern(&pattern)?;
+        Ok(SuffixMatcher { pattern })
+    }
+}
+
+impl Matcher for SuffixMatcher {
+    fn is_match(&self, address: &str) -> bool {
+        match address.strip_suffix(self.pattern.as_str()) {
+            Some(rest) => boundary_ok(&self.pattern, rest.chars().next_back()),
+            None => false,
+        }
+    }
+
+    fn probability(&self) -> f64 {
+        literal_probability(self.pattern.chars().count()) * boundary_probability(&self.pattern)
+    }
+
+    fn describe(&self) -> String {
+        format!("suffix {:?}", self.pattern)
+    }
+}
+
+/// Address has both a prefix and a suffix
+#[derive(Debug, Clone)]
+pub struct BothMatcher {
+    prefix: PrefixMatcher,
+    suffix: SuffixMatcher,
+}
+
+impl BothMatcher {
+    pub fn new(prefix: impl Into<String>, suffix: impl Into<String>) -> Result<Self, VanityError> {
+        Ok(BothMatcher { prefix: PrefixMatcher::new(prefix)?, suffix: SuffixMatcher::new(suffix)? })
+    }
+}
+
+impl Matcher for BothMatcher {
+    fn is_match(&self, address: &str) -> bool {
+        self.prefix.is_match(address) && self.suffix.is_match(address)
+    }
+
+    fn probability(&self) -> f64 {
+        self.prefix.probability() * self.suffix.probability()
+    }
+
+    fn describe(&self) -> String {
+        format!("{} and {}", self.prefix.describe(), self.suffix.describe())
+    }
+}
+
+/// Address matches a regular expression
+#[derive(Debug, Clone)]
+pub struct RegexMatcher {
+    regex: Regex,
+    probability: f64,
+}
+
+impl RegexMatcher {
+    /// The probability is estimated from the number of literal Base58 characters in the pattern;
+    /// classes and repetition are ignored, so treat it as an order of magnitude and override it
+    /// with `with_probability` when the real figure is known.
+    pub fn new(pattern: &str) -> Result<Self, VanityError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| VanityError::InvalidInput(format!("invalid regex {:?}: {}", pattern, e)))?;
+        let literals = pattern.chars().filter(|c| BASE58_ALPHABET.contains(*c)).count();
+        Ok(RegexMatcher { regex, probability: literal_probability(literals) })
+    }
+
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn is_match(&self, address: &str) -> bool {
+        self.regex.is_match(address)
+    }
+
+    fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    fn describe(&self) -> String {
+        format!("regex {:?}", self.regex.as_str())
+    }
+}
+
+/// Matches when every inner matcher matches (probabilities assumed independent)
+pub struct AllOf(pub Vec<Box<dyn Matcher>>);
+
+impl Matcher for AllOf {
+    fn is_match(&self, address: &str) -> bool {
+        self.0.iter().all(|m| m.is_match(address))
+    }
+
+    fn probability(&self) -> f64 {
+        self.0.iter().map(|m| m.probability()).product()
+    }
+
+    fn describe(&self) -> String {
+        self.0.iter().map(|m| m.describe()).collect::<Vec<_>>().join(" and ")
+    }
+}
+
+/// Matches when any inner matcher matches (probabilities assumed independent)
+pub struct AnyOf(pub Vec<Box<dyn Matcher>>);
+
+impl Matcher for AnyOf {
+    fn is_match(&self, address: &str) -> bool {
+        self.0.iter().any(|m| m.is_match(address))
+    }
+
+    fn probability(&self) -> f64 {
+        1.0 - self.0.iter().map(|m| 1.0 - m.probability()).product::<f64>()
+    }
+
+    fn describe(&self) -> String {
+        self.0.iter().map(|m| m.describe()).collect::<Vec<_>>().join(" or ")
+    }
+}
+
+impl SearchMode {
+    /// The built-in matcher for this mode
+    pub fn matcher(&self) -> Result<Box<dyn Matcher>, VanityError> {
+        Ok(match self {
+            SearchMode::Prefix(p) => Box::new(PrefixMatcher::new(p.clone())?),
+            SearchMode::Suffix(s) => Box::new(SuffixMatcher::new(s.clone())?),
+            SearchMode::Both { prefix, suffix } => Box::new(BothMatcher::new(prefix.clone(), suffix.clone())?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn prefix(p: &str) -> PrefixMatcher {
+        PrefixMatcher::new(p).unwrap()
+    }
+
+    fn suffix(s: &str) -> SuffixMatcher {
+        SuffixMatcher::new(s).unwrap()
+    }
+
+    #[test]
+    fn uppercase_pattern_needs_lowercase_neighbor() {
+        assert!(prefix("ABC").is_match("ABCabc"));
+        assert!(!prefix("ABC").is_match("ABCAbc"));
+        assert!(!prefix("ABC").is_match("ABC1bc"));
+        assert!(suffix("ABC").is_match("abcABC"));
+        assert!(!suffix("ABC").is_match("abCABC"));
+    }
+
+    #[test]
+    fn lowercase_pattern_needs_uppercase_neighbor() {
+        assert!(prefix("xyz").is_match("xyzAbc"));
+        assert!(!prefix("xyz").is_match("xyzabc"));
+        assert!(!prefix("xyz").is_match("xyz9bc"));
+        assert!(suffix("xyz").is_match("abCxyz"));
+        assert!(!suffix("xyz").is_match("abcxyz"));
+    }
+
+    #[test]
+    fn digit_pattern_needs_letter_neighbor() {
+        assert!(prefix("777").is_match("777abc"));
+        assert!(prefix("777").is_match("777Abc"));
+        assert!(!prefix("777").is_match("7778bc"));
+        assert!(suffix("42").is_match("abz42"));
+        assert!(!suffix("42").is_match("ab142"));
+    }
+
+    #[test]
+    fn prefix_and_suffix_may_overlap() {
+        let both = BothMatcher::new("AB", "Bc").unwrap();
+        // "ABc" satisfies both patterns by sharing the "B"; real addresses are far longer than any pattern
+        assert!(both.is_match("ABc"));
+        assert!(both.is_match("ABcBc"));
+        assert!(!both.is_match("xABBc"));
+    }
+
+    #[test]
+    fn mixed_pattern_accepts_any_neighbor() {
+        for address in ["Sun1xyz", "Sunaxyz", "SunAxyz"] {
+            assert!(prefix("Sun").is_match(address), "{}", address);
+        }
+        assert!(suffix("Sun").is_match("xyz9Sun"));
+    }
+
+    #[test]
+    fn pattern_must_have_a_neighbor() {
+        assert!(!prefix("Sun").is_match("Sun"));
+        assert!(!suffix("Sun").is_match("Sun"));
+        assert!(!prefix("Sun").is_match("So"));
+    }
+
+    #[test]
+    fn multi_byte_input_does_not_match_or_panic() {
+        assert!(!prefix("Sun").is_match("Sünny"));
+        assert!(!prefix("Sun").is_match(""));
+        assert!(prefix("Sun").is_match("Suné"));
+        assert!(!suffix("xyz").is_match("ÀÉxyz"));
+        assert!(suffix("Sun").is_match("éSun"));
+        assert!(!BothMatcher::new("A", "b").unwrap().is_match("Aé"));
+    }
+
+    #[test]
+    fn patterns_are_validated() {
+        assert!(PrefixMatcher::new("").is_err());
+        assert!(PrefixMatcher::new("Sun0").is_err());
+        assert!(SuffixMatcher::new("lI").is_err());
+        assert!(RegexMatcher::new("(").is_err());
+    }
+
+    #[test]
+    fn probability_accounts_for_case_rules() {
+        // 25 lowercase letters in the alphabet may follow an uppercase pattern
+        let expected = (1.0 / 58.0) * (25.0 / 58.0);
+        assert!((prefix("A").probability() - expected).abs() < 1e-12);
+        let mixed = prefix("Ab").probability();
+        assert!((mixed - 1.0 / (58.0 * 58.0)).abs() < 1e-12);
+        let both = BothMatcher::new("Ab", "Cd").unwrap().probability();
+        assert!((both - mixed * mixed).abs() < 1e-15);
+    }
+
+    #[test]
+    fn composites_combine_matchers() {
+        let all = AllOf(vec![Box::new(prefix("Ab")), Box::new(RegexMatcher::new("z$").unwrap())]);
+        assert!(all.is_match("Abxz"));
+        assert!(!all.is_match("Abxy"));
+        let any = AnyOf(vec![Box::new(prefix("Ab")), Box::new(suffix("Cd"))]);
+        assert!(any.is_match("Abxy"));
+        assert!(any.is_match("xyCd"));
+        assert!(!any.is_match("xyxy"));
+        let p = 0.25;
+        let any = AnyOf(vec![
+            Box::new(RegexMatcher::new("a").unwrap().with_probability(p)),
+            Box::new(RegexMatcher::new("b").unwrap().with_probability(p)),
+        ]);
+        assert!((any.probability() - (1.0 - 0.75 * 0.75)).abs() < 1e-12);
+    }
+
+    fn base58(len: std::ops::RangeInclusive<usize>) -> impl Strategy<Value = String> {
+        proptest::collection::vec(proptest::sample::select(BASE58_ALPHABET.chars().collect::<Vec<_>>()), len)
+            .prop_map(|chars| chars.into_iter().collect())
+    }
+
+    proptest! {
+        #[test]
+        fn prefix_match_implies_starts_with(pattern in base58(1..=4), address in base58(0..=44)) {
+            if prefix(&pattern).is_match(&address) {
+                prop_assert!(address.starts_with(&pattern));
+                prop_assert!(address.len() > pattern.len());
+            }
+        }
+
+        #[test]
+        fn suffix_match_implies_ends_with(pattern in base58(1..=4), address in base58(0..=44)) {
+            if suffix(&pattern).is_match(&address) {
+                prop_assert!(address.ends_with(&pattern));
+                prop_assert!(address.len() > pattern.len());
+            }
+        }
+
+        #[test]
+        fn prefix_matches_with_a_valid_neighbor(pattern in base58(1..=4), rest in base58(1..=40)) {
+            let address = format!("{}{}", pattern, rest);
+            let neighbor = rest.chars().next();
+            prop_assert_eq!(prefix(&pattern).is_match(&address), boundary_ok(&pattern, neighbor));
+        }
+
+        #[test]
+        fn suffix_matches_with_a_valid_neighbor(pattern in base58(1..=4), rest in base58(1..=40)) {
+            let address = format!("{}{}", rest, pattern);
+            let neighbor = rest.chars().next_back();
+            prop_assert_eq!(suffix(&pattern).is_match(&address), boundary_ok(&pattern, neighbor));
+        }
+
+        #[test]
+        fn both_checks_each_end_against_its_neighbor(
+            p in base58(1..=3),
+            middle in base58(0..=6),
+            s in base58(1..=3),
+        ) {
+            let address = format!("{}{}{}", p, middle, s);
+            let after_prefix = address[p.len()..].chars().next();
+            let before_suffix = address[..address.len() - s.len()].chars().next_back();
+            let expected = boundary_ok(&p, after_prefix) && boundary_ok(&s, before_suffix);
+            prop_assert_eq!(BothMatcher::new(p.clone(), s.clone()).unwrap().is_match(&address), expected);
+        }
+
+        #[test]
+        fn arbitrary_input_never_panics(pattern in base58(1..=4), address in ".{0,50}") {
+            let _ = prefix(&pattern).is_match(&address);
+            let _ = suffix(&pattern).is_match(&address);
+            let _ = BothMatcher::new(pattern.clone(), pattern.clone()).unwrap().is_match(&address);
+        }
+
+        #[test]
+        fn probability_is_within_bounds(pattern in base58(1..=8)) {
+            for p in [prefix(&pattern).probability(), suffix(&pattern).probability()] {
+                prop_assert!(p > 0.0 && p <= 1.0 / 58.0);
+            }
+        }
+    }
+}
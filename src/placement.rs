@@ -0,0 +1,188 @@
+use crate::error::VanityError;
+use std::io;
+
+/// Scheduling priority and CPU pinning applied to each worker thread of a search
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadPlacement {
+    /// Niceness for the workers (-20..=19, higher yields more to other work; Unix only).
+    /// Negative values usually need elevated privileges.
+    pub nice: Option<i32>,
+    /// Pin worker `i` to CPU `cpus[i % cpus.len()]` (Linux only); empty leaves placement to the OS
+    pub cpus: Vec<usize>,
+}
+
+impl ThreadPlacement {
+    /// Check the settings before any worker starts
+    pub fn validate(&self) -> Result<(), VanityError> {
+        if let Some(nice) = self.nice {
+            if !(-20..=19).contains(&nice) {
+                return Err(VanityError::InvalidInput(format!("niceness must be between -20 and 19, got {}", nice)));
+            }
+            if !cfg!(unix) {
+                return Err(VanityError::InvalidInput("thread niceness is only supported on Unix".to_string()));
+            }
+        }
+        if !self.cpus.is_empty() {
+            if !cfg!(target_os = "linux") {
+                return Err(VanityError::InvalidInput("CPU pinning is only supported on Linux".to_string()));
+            }
+            // The CPUs this process may run on, which cgroups and taskset can narrow to any subset
+            let allowed = allowed_cpus().map_err(|e| VanityError::io("cannot read the CPU affinity", e))?;
+            if let Some(cpu) = self.cpus.iter().find(|cpu| !allowed.contains(cpu)) {
+                return Err(VanityError::InvalidInput(format!(
+                    "CPU {} is not available to this process (allowed: {})",
+                    cpu,
+                    cpu_ranges(&allowed)
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the settings to the calling thread, which is worker number `worker`
+    pub(crate) fn apply(&self, worker: usize) -> Result<(), VanityError> {
+        if let Some(nice) = self.nice {
+            set_nice(nice).map_err(|e| VanityError::io(format!("cannot set worker niceness to {}", nice), e))?;
+        }
+        if !self.cpus.is_empty() {
+            let cpu = self.cpus[worker % self.cpus.len()];
+            pin_to(cpu).map_err(|e| VanityError::io(format!("cannot pin worker {} to CPU {}", worker, cpu), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Set the niceness of the calling thread only (Linux threads have their own priority)
+#[cfg(unix)]
+fn set_nice(nice: i32) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    // SAFETY: gettid has no preconditions and only returns the caller's thread id
+    let who = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    // Elsewhere only the whole process can be reniced
+    #[cfg(not(target_os = "linux"))]
+    let who = 0;
+    // SAFETY: plain syscall on the calling thread/process; no memory is passed
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, who, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "thread niceness is only supported on Unix"))
+}
+
+/// Format sorted CPU ids as ranges, e.g. `0-3,6`
+fn cpu_ranges(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect();
+    ranges.join(",")
+}
+
+/// CPUs the process is allowed to run on, in ascending order
+#[cfg(target_os = "linux")]
+pub(crate) fn allowed_cpus() -> io::Result<Vec<usize>> {
+    // SAFETY: cpu_set_t is a plain bitmask, valid when zeroed; getpid has no preconditions and names
+    // the process (its main thread), whose affinity the workers inherit
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(libc::getpid(), std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((0..libc::CPU_SETSIZE as usize).filter(|&cpu| libc::CPU_ISSET(cpu, &set)).collect())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn allowed_cpus() -> io::Result<Vec<usize>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU pinning is only supported on Linux"))
+}
+
+/// Restrict the calling thread to a single CPU
+#[cfg(target_os = "linux")]
+fn pin_to(cpu: usize) -> io::Result<()> {
+    // SAFETY: cpu_set_t is a plain bitmask, valid when zeroed (CPU_SET bounds-checks `cpu`),
+    // and pid 0 means the calling thread
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to(_cpu: usize) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU pinning is only supported on Linux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_out_of_range_settings() {
+        let nice = ThreadPlacement { nice: Some(20), ..Default::default() };
+        assert!(matches!(nice.validate(), Err(VanityError::InvalidInput(_))));
+        assert!(ThreadPlacement::default().validate().is_ok());
+    }
+
+    #[test]
+    fn formats_cpu_ranges() {
+        assert_eq!(cpu_ranges(&[0, 1, 2, 3, 6]), "0-3,6");
+        assert_eq!(cpu_ranges(&[4, 5, 6, 7]), "4-7");
+        assert_eq!(cpu_ranges(&[2]), "2");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_allowed_cpus_pass_validation() {
+        let allowed = allowed_cpus().unwrap();
+        let pinned = ThreadPlacement { cpus: allowed.clone(), ..Default::default() };
+        assert!(pinned.validate().is_ok());
+        let outside = (0..).find(|cpu| !allowed.contains(cpu)).unwrap();
+        let cpus = ThreadPlacement { cpus: vec![outside], ..Default::default() };
+        assert!(matches!(cpus.validate(), Err(VanityError::InvalidInput(_))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn applies_to_the_calling_thread_only() {
+        // Any CPU this process may use; CPU 0 can be outside a container's cpuset
+        let cpu = allowed_cpus().unwrap()[0];
+        let placement = ThreadPlacement { nice: Some(19), cpus: vec![cpu] };
+        // SAFETY: plain query of the calling thread's priority
+        let before = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        placement.validate().unwrap();
+        std::thread::spawn(move || {
+            placement.apply(0).unwrap();
+            // SAFETY: as in `set_nice`/`pin_to`
+            unsafe {
+                let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+                assert_eq!(libc::getpriority(libc::PRIO_PROCESS, tid), 19);
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set);
+                assert_eq!(libc::CPU_COUNT(&set), 1);
+                assert!(libc::CPU_ISSET(cpu, &set));
+            }
+        })
+        .join()
+        .unwrap();
+        // SAFETY: as above
+        assert_eq!(unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) }, before);
+    }
+}
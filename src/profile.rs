@@ -0,0 +1,383 @@
+//! Named executor profiles, read from a TOML file that a team can check in next to its jobs.
+//!
+//! ```toml
+//! [profiles.gpu]
+//! type = "cloud-run"
+//! job = "vanity-gpu-job"
+//! image = "europe-docker.pkg.dev/acme/vanity/gpu:1.4"
+//! threads = 32
+//! limits = { cpus = 8, memory_mib = 16384, gpus = 1 }
+//! command = ["--nice", "5", "--threads", "{threads}", "{search}"]
+//! ```
+
+use crate::container::{ContainerEngine, ContainerExecutor, DEFAULT_IMAGE};
+use crate::cost::CostModel;
+use crate::error::VanityError;
+use crate::executor::{
+    AwsBatchExecutor, CloudRunExecutor, CommandRunner, Executor, JobSpec, LocalProcessExecutor, ResourceLimits,
+};
+use crate::ssh::{RemoteBinary, SshExecutor, SshHost};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config file looked up in the working directory when no `--config` is given
+pub const DEFAULT_CONFIG_FILE: &str = "vanity.toml";
+
+/// Job name used when a profile does not set one
+const DEFAULT_JOB: &str = "vanity-search";
+
+/// Which executor a profile submits to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileKind {
+    /// Background process on this machine
+    Process,
+    /// AWS Batch job queue
+    AwsBatch,
+    /// Google Cloud Run job
+    CloudRun,
+    /// Your own machines over SSH
+    Ssh,
+    /// Local Docker container
+    Docker,
+    /// Local Podman container
+    Podman,
+}
+
+/// How to submit a search: the executor, where it runs and the command line it gets
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(rename = "type")]
+    pub kind: ProfileKind,
+    /// AWS Batch job name or Cloud Run job to execute
+    pub job: Option<String>,
+    /// AWS Batch job queue (required for `aws-batch`)
+    pub queue: Option<String>,
+    /// AWS Batch job definition (defaults to the job name)
+    pub job_definition: Option<String>,
+    /// Container image the Cloud Run job is switched to before executing, or the image a `docker`/`podman`
+    /// profile runs (default: built from ./Dockerfile)
+    pub image: Option<String>,
+    /// Build the `docker`/`podman` image from the Dockerfile in this directory before each run
+    pub build: Option<PathBuf>,
+    /// Directory that collects `docker`/`podman` results, one subdirectory per job (default: vanity-results)
+    pub results_dir: Option<PathBuf>,
+    /// SSH hosts, as `DEST` or `DEST=THREADS` (required for `ssh`)
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Copy this binary to the SSH hosts instead of using an installed one
+    #[serde(default)]
+    pub upload: bool,
+    /// Installed program the SSH hosts run (default: solana-vanity-seed on their PATH)
+    pub program: Option<String>,
+    /// Search threads on the executor (defaults to `--threads`)
+    pub threads: Option<usize>,
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    /// Arguments passed to the remote `solana-vanity-seed`; `{search}` expands to the search options,
+    /// `{threads}` and `{job}` are substituted anywhere
+    #[serde(default = "default_command")]
+    pub command: Vec<String>,
+    /// Price of the executor while a search runs, for cost estimates (default: free)
+    pub usd_per_hour: Option<f64>,
+    /// Measured search rate on the executor (default: this machine's per-thread rate × threads, unless
+    /// the profile requests GPUs)
+    pub keys_per_sec: Option<f64>,
+}
+
+fn default_command() -> Vec<String> {
+    ["--threads", "{threads}", "{search}"].map(String::from).to_vec()
+}
+
+impl Profile {
+    /// A profile for `kind` with every optional setting left at its default
+    pub fn new(kind: ProfileKind) -> Self {
+        Profile {
+            kind,
+            job: None,
+            queue: None,
+            job_definition: None,
+            image: None,
+            build: None,
+            results_dir: None,
+            hosts: Vec::new(),
+            upload: false,
+            program: None,
+            threads: None,
+            limits: ResourceLimits::default(),
+            command: default_command(),
+            usd_per_hour: None,
+            keys_per_sec: None,
+        }
+    }
+
+    /// Name of the job submitted through this profile
+    pub fn job_name(&self) -> &str {
+        self.job.as_deref().unwrap_or(DEFAULT_JOB)
+    }
+
+    /// Reject settings the profile's executor would otherwise silently ignore
+    pub fn validate(&self) -> Result<(), VanityError> {
+        let unsupported = |setting: &str| {
+            Err(VanityError::InvalidInput(format!("{} is not supported by the {:?} executor", setting, self.kind)))
+        };
+        let ssh = self.kind == ProfileKind::Ssh;
+        if !ssh && (!self.hosts.is_empty() || self.upload || self.program.is_some()) {
+            return unsupported("hosts/upload/program");
+        }
+        let container = matches!(self.kind, ProfileKind::Docker | ProfileKind::Podman);
+        if !container && (self.build.is_some() || self.results_dir.is_some()) {
+            return unsupported("build/results_dir");
+        }
+        match self.kind {
+            ProfileKind::Process | ProfileKind::Ssh => {
+                if self.queue.is_some() || self.job_definition.is_some() {
+                    return unsupported("queue/job_definition");
+                }
+                if self.image.is_some() {
+                    return unsupported("image");
+                }
+                if !self.limits.is_empty() {
+                    return unsupported("limits");
+                }
+            }
+            ProfileKind::AwsBatch => {
+                if self.queue.is_none() {
+                    return Err(VanityError::InvalidInput("aws-batch profiles need a queue".to_string()));
+                }
+                if self.image.is_some() {
+                    return unsupported("image (set it in the job definition)");
+                }
+            }
+            ProfileKind::CloudRun | ProfileKind::Docker | ProfileKind::Podman => {
+                if self.queue.is_some() || self.job_definition.is_some() {
+                    return unsupported("queue/job_definition");
+                }
+            }
+        }
+        if ssh {
+            if self.hosts.is_empty() {
+                return Err(VanityError::InvalidInput("ssh profiles need at least one host".to_string()));
+            }
+            if self.upload && self.program.is_some() {
+                return Err(VanityError::InvalidInput("set either upload or program, not both".to_string()));
+            }
+            self.ssh_hosts()?;
+        }
+        if self.threads == Some(0) {
+            return Err(VanityError::InvalidInput("threads must be at least 1".to_string()));
+        }
+        let invalid_usd = self.usd_per_hour.is_some_and(|usd| !usd.is_finite() || usd < 0.0);
+        let invalid_rate = self.keys_per_sec.is_some_and(|rate| !rate.is_finite() || rate <= 0.0);
+        if invalid_usd || invalid_rate {
+            return Err(VanityError::InvalidInput(
+                "usd_per_hour must not be negative and keys_per_sec must be positive".to_string(),
+            ));
+        }
+        if !self.command.iter().any(|arg| arg == "{search}") {
+            return Err(VanityError::InvalidInput("command must contain a \"{search}\" argument".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Build the job from the search options, filling in the command template
+    pub fn job(&self, search: &[String], threads: usize) -> JobSpec {
+        let threads = self.threads.unwrap_or(threads).to_string();
+        let mut args = Vec::new();
+        for arg in &self.command {
+            if arg == "{search}" {
+                args.extend_from_slice(search);
+            } else {
+                args.push(arg.replace("{threads}", &threads).replace("{job}", self.job_name()));
+            }
+        }
+        JobSpec { name: self.job_name().to_string(), args }
+    }
+
+    /// Rate and price for estimates; without `keys_per_sec`, assume the executor's threads run at
+    /// `per_thread_rate`. None for a GPU profile without `keys_per_sec`, whose rate a CPU cannot predict.
+    pub fn cost_model(&self, per_thread_rate: f64, threads: usize) -> Option<CostModel> {
+        let keys_per_sec = match self.keys_per_sec {
+            Some(rate) => rate,
+            None if self.limits.gpus.is_some() => return None,
+            None => per_thread_rate * self.threads.unwrap_or(threads) as f64,
+        };
+        Some(CostModel { keys_per_sec, usd_per_hour: self.usd_per_hour.unwrap_or(0.0) })
+    }
+
+    fn ssh_hosts(&self) -> Result<Vec<SshHost>, VanityError> {
+        self.hosts.iter().map(|host| host.parse().map_err(VanityError::InvalidInput)).collect()
+    }
+
+    /// Create the executor; `program` is what a `process` profile runs (and `ssh` uploads), `runner` what
+    /// the cloud and container ones call
+    pub fn executor(
+        &self,
+        program: impl Into<PathBuf>,
+        runner: impl CommandRunner + 'static,
+    ) -> Result<Box<dyn Executor>, VanityError> {
+        self.validate()?;
+        Ok(match self.kind {
+            ProfileKind::Process => Box::new(LocalProcessExecutor::new(program, ".")),
+            ProfileKind::AwsBatch => {
+                let queue = self.queue.as_deref().unwrap_or_default();
+                let definition = self.job_definition.as_deref().unwrap_or(self.job_name());
+                Box::new(AwsBatchExecutor::new(queue, definition, runner).limits(self.limits))
+            }
+            ProfileKind::CloudRun => Box::new(CloudRunExecutor::new(self.image.clone(), runner).limits(self.limits)),
+            ProfileKind::Ssh => {
+                let binary = if self.upload {
+                    RemoteBinary::upload(program)?
+                } else {
+                    RemoteBinary::Installed(self.program.clone().unwrap_or_else(|| "solana-vanity-seed".to_string()))
+                };
+                Box::new(SshExecutor::new(self.ssh_hosts()?, binary))
+            }
+            ProfileKind::Docker | ProfileKind::Podman => {
+                let engine =
+                    if self.kind == ProfileKind::Docker { ContainerEngine::Docker } else { ContainerEngine::Podman };
+                let image = self.image.as_deref().unwrap_or(DEFAULT_IMAGE);
+                let mut executor = ContainerExecutor::new(engine, image, runner).limits(self.limits);
+                // Without an image to run, build the repo's own
+                match (&self.build, &self.image) {
+                    (Some(context), _) => executor = executor.build(context),
+                    (None, None) => executor = executor.build("."),
+                    (None, Some(_)) => {}
+                }
+                if let Some(dir) = &self.results_dir {
+                    executor = executor.results_dir(dir);
+                }
+                Box::new(executor)
+            }
+        })
+    }
+}
+
+/// Contents of a profile config file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ProfileConfig {
+    /// Read and check every profile in `path`
+    pub fn load(path: &Path) -> Result<Self, VanityError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| VanityError::io(format!("cannot read config file {}", path.display()), e))?;
+        Self::parse(&text).map_err(|e| VanityError::InvalidInput(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse and check a config from TOML text
+    pub fn parse(text: &str) -> Result<Self, VanityError> {
+        let config: ProfileConfig = toml::from_str(text).map_err(|e| VanityError::InvalidInput(e.message().to_string()))?;
+        for (name, profile) in &config.profiles {
+            profile
+                .validate()
+                .map_err(|e| VanityError::InvalidInput(format!("profile {:?}: {}", name, e)))?;
+        }
+        Ok(config)
+    }
+
+    /// Look up a profile by name
+    pub fn profile(&self, name: &str) -> Result<&Profile, VanityError> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            let known = if known.is_empty() { "none; no config file was found".to_string() } else { known.join(", ") };
+            VanityError::InvalidInput(format!("no profile named {:?} (available: {})", name, known))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::SystemRunner;
+
+    const CONFIG: &str = r#"
+        [profiles.cpu]
+        type = "aws-batch"
+        job = "vanity-search-cpu"
+        queue = "spot-cpu"
+        threads = 16
+        limits = { cpus = 16, memory_mib = 4096 }
+        usd_per_hour = 0.68
+
+        [profiles.gpu]
+        type = "cloud-run"
+        job = "vanity-gpu-job"
+        image = "europe-docker.pkg.dev/acme/vanity/gpu:1.4"
+        command = ["--nice", "5", "--threads", "{threads}", "{search}"]
+        keys_per_sec = 250000.0
+    "#;
+
+    #[test]
+    fn profiles_fill_in_the_command_template() {
+        let config = ProfileConfig::parse(CONFIG).unwrap();
+        let search = ["--suffix".to_string(), "xyz".to_string()];
+        let cpu = config.profile("cpu").unwrap();
+        assert_eq!(cpu.job(&search, 4).args, ["--threads", "16", "--suffix", "xyz"]);
+        let gpu = config.profile("gpu").unwrap();
+        let job = gpu.job(&search, 4);
+        assert_eq!(job.name, "vanity-gpu-job");
+        assert_eq!(job.args, ["--nice", "5", "--threads", "4", "--suffix", "xyz"]);
+        let plan = gpu.executor("unused", SystemRunner).unwrap().plan(&job);
+        assert_eq!(plan[0][6], "europe-docker.pkg.dev/acme/vanity/gpu:1.4");
+        let plan = cpu.executor("unused", SystemRunner).unwrap().plan(&cpu.job(&search, 4));
+        assert!(plan[0].contains(&"spot-cpu".to_string()));
+        assert!(plan[0].iter().any(|arg| arg.contains(r#""resourceRequirements""#)));
+    }
+
+    #[test]
+    fn cost_models_fall_back_to_the_calibrated_rate() {
+        let config = ProfileConfig::parse(CONFIG).unwrap();
+        let cpu = config.profile("cpu").unwrap().cost_model(1_000.0, 4);
+        assert_eq!(cpu, Some(CostModel { keys_per_sec: 16_000.0, usd_per_hour: 0.68 }));
+        let gpu = config.profile("gpu").unwrap().cost_model(1_000.0, 4);
+        assert_eq!(gpu, Some(CostModel { keys_per_sec: 250_000.0, usd_per_hour: 0.0 }));
+        let unmeasured = ProfileConfig::parse("[profiles.g]\ntype = \"cloud-run\"\nlimits = { gpus = 1 }").unwrap();
+        assert_eq!(unmeasured.profile("g").unwrap().cost_model(1_000.0, 4), None);
+    }
+
+    #[test]
+    fn container_profiles_build_the_image_unless_given_one() {
+        let search = ["--suffix".to_string(), "x".to_string()];
+        let built = ProfileConfig::parse("[profiles.c]\ntype = \"docker\"\nlimits = { cpus = 2 }").unwrap();
+        let built = built.profile("c").unwrap();
+        let plan = built.executor("unused", SystemRunner).unwrap().plan(&built.job(&search, 4));
+        assert_eq!(plan[0][..5], ["docker", "build", "--quiet", "--tag", DEFAULT_IMAGE]);
+        assert!(plan[1].windows(2).any(|w| w == ["--cpus", "2"]), "{:?}", plan[1]);
+        let pulled = ProfileConfig::parse("[profiles.c]\ntype = \"podman\"\nimage = \"ghcr.io/acme/vanity:2\"").unwrap();
+        let pulled = pulled.profile("c").unwrap();
+        let plan = pulled.executor("unused", SystemRunner).unwrap().plan(&pulled.job(&search, 4));
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0][..2], ["podman", "run"]);
+        assert!(plan[0].contains(&"ghcr.io/acme/vanity:2".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_profiles() {
+        for (config, expected) in [
+            ("[profiles.a]\ntype = \"aws-batch\"", "need a queue"),
+            ("[profiles.a]\ntype = \"process\"\nlimits = { gpus = 1 }", "limits"),
+            ("[profiles.a]\ntype = \"cloud-run\"\ncommand = [\"--raw\"]", "{search}"),
+            ("[profiles.a]\ntype = \"cloud-run\"\nimgae = \"x\"", "imgae"),
+            ("[profiles.a]\ntype = \"k8s\"", "k8s"),
+            ("[profiles.a]\ntype = \"ssh\"", "at least one host"),
+            ("[profiles.a]\ntype = \"ssh\"\nhosts = [\"a=x\"]", "thread count"),
+            ("[profiles.a]\ntype = \"cloud-run\"\nhosts = [\"a\"]", "hosts"),
+            ("[profiles.a]\ntype = \"cloud-run\"\nbuild = \".\"", "build"),
+            ("[profiles.a]\ntype = \"docker\"\nqueue = \"q\"", "queue"),
+            ("[profiles.a]\ntype = \"process\"\nkeys_per_sec = 0.0", "keys_per_sec"),
+        ] {
+            let err = ProfileConfig::parse(config).unwrap_err().to_string();
+            assert!(err.contains(expected), "{}: {}", config, err);
+        }
+        let err = ProfileConfig::parse(CONFIG).unwrap().profile("tpu").unwrap_err().to_string();
+        assert!(err.contains("available: cpu, gpu"), "{}", err);
+    }
+}
@@ -0,0 +1,658 @@
+//! Queue of searches behind `serve`: jobs wait by priority for one of `max_jobs` slots, then run the
+//! ordinary search loop with their own counters, limits and result.
+//!
+//! With a store directory the queue survives restarts. The job list (results included, until fetched)
+//! is kept in `jobs.json`, and each running search checkpoints to `job-ID.json` like `--state-file`,
+//! so a job interrupted by a shutdown is queued again and resumes with its attempts and elapsed time.
+
+use crate::error::VanityError;
+use crate::executor::JobStatus;
+use crate::placement::ThreadPlacement;
+use crate::search::{GenerationMode, Progress, SearchEvent, SearchLimits, VanitySearch};
+use crate::sink::SearchResult;
+use crate::state::{save_secret_json, Checkpoint, SearchConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Job list in a store directory
+const JOBS_FILE: &str = "jobs.json";
+
+/// A search to run, as posted to `/jobs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobRequest {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub regex: Option<String>,
+    /// `raw`, `mnemonic` (default) or `token`
+    #[serde(default = "default_generation")]
+    pub generation: GenerationMode,
+    /// Mnemonic length, 12 (default) or 24
+    #[serde(default = "default_words")]
+    pub words: usize,
+    /// Worker threads (default: the service's)
+    pub threads: Option<usize>,
+    /// Budget over the job's whole life, restarts included
+    pub max_time_secs: Option<u64>,
+    pub max_attempts: Option<u64>,
+    /// Queued jobs with a higher priority start first (default 0; ties in submission order)
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Longest `max_time_secs` accepted (ten years); longer budgets are no budget at all
+const MAX_TIME_SECS: u64 = 10 * 365 * 86_400;
+
+fn default_generation() -> GenerationMode {
+    GenerationMode::Mnemonic
+}
+
+fn default_words() -> usize {
+    12
+}
+
+/// A failed API call: the HTTP status and a message for the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    pub(crate) fn new(status: u16, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into() }
+    }
+
+    fn not_found(id: u64) -> Self {
+        ApiError::new(404, format!("no job {}", id))
+    }
+}
+
+/// Whether a job's match can still be fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultState {
+    /// No match (yet)
+    None,
+    Available,
+    /// Fetched once; the service no longer holds the secrets
+    Fetched,
+}
+
+/// What the API reports about a job (never its secrets)
+#[derive(Debug, Clone, Serialize)]
+pub struct JobView {
+    pub id: u64,
+    /// `queued`, `running`, `succeeded`, `failed` or `cancelled`
+    pub status: &'static str,
+    /// Why the job failed
+    pub reason: Option<String>,
+    pub request: JobRequest,
+    /// Latest progress report (every `progress_interval`), or the final accounting
+    pub progress: Option<Progress>,
+    pub public_address: Option<String>,
+    pub result: ResultState,
+}
+
+fn status_name(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::Pending => "queued",
+        JobStatus::Running => "running",
+        JobStatus::Succeeded => "succeeded",
+        JobStatus::Failed { .. } => "failed",
+        JobStatus::Cancelled => "cancelled",
+    }
+}
+
+/// A job as kept in the store's job list
+#[derive(Serialize, Deserialize)]
+struct StoredJob {
+    id: u64,
+    request: JobRequest,
+    status: String,
+    reason: Option<String>,
+    progress: Option<Progress>,
+    public_address: Option<String>,
+    result: Option<SearchResult>,
+}
+
+struct Job {
+    request: JobRequest,
+    status: JobStatus,
+    progress: Option<Progress>,
+    stop: Arc<AtomicBool>,
+    public_address: Option<String>,
+    result: Option<SearchResult>,
+    /// The search thread while the job runs
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Job {
+    fn new(request: JobRequest) -> Self {
+        Job {
+            request,
+            status: JobStatus::Pending,
+            progress: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            public_address: None,
+            result: None,
+            thread: None,
+        }
+    }
+
+    fn view(&self, id: u64) -> JobView {
+        let result = match (&self.result, &self.status) {
+            (Some(_), _) => ResultState::Available,
+            (None, JobStatus::Succeeded) => ResultState::Fetched,
+            (None, _) => ResultState::None,
+        };
+        JobView {
+            id,
+            status: status_name(&self.status),
+            reason: match &self.status {
+                JobStatus::Failed { reason } => Some(reason.clone()),
+                _ => None,
+            },
+            request: self.request.clone(),
+            progress: self.progress.clone(),
+            public_address: self.public_address.clone(),
+            result,
+        }
+    }
+
+    fn stored(&self, id: u64) -> StoredJob {
+        let view = self.view(id);
+        StoredJob {
+            id,
+            request: view.request,
+            status: view.status.to_string(),
+            reason: view.reason,
+            progress: view.progress,
+            public_address: view.public_address,
+            result: self.result.clone(),
+        }
+    }
+
+    fn restore(stored: StoredJob) -> Self {
+        let status = match stored.status.as_str() {
+            "succeeded" => JobStatus::Succeeded,
+            "failed" => JobStatus::Failed { reason: stored.reason.unwrap_or_else(|| "no reason given".to_string()) },
+            "cancelled" => JobStatus::Cancelled,
+            // Running jobs were interrupted by the shutdown and resume from their checkpoint
+            _ => JobStatus::Pending,
+        };
+        Job {
+            status,
+            progress: stored.progress,
+            public_address: stored.public_address,
+            result: stored.result,
+            ..Job::new(stored.request)
+        }
+    }
+}
+
+/// Directory a service keeps its jobs in
+struct Store {
+    dir: PathBuf,
+    checkpoint_interval: Duration,
+}
+
+impl Store {
+    fn jobs_path(&self) -> PathBuf {
+        self.dir.join(JOBS_FILE)
+    }
+
+    fn checkpoint_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("job-{}.json", id))
+    }
+}
+
+/// Runs submitted searches in this process, at most `max_jobs` at a time, and keeps their outcome
+pub struct SearchService {
+    threads: usize,
+    max_jobs: usize,
+    progress_interval: Duration,
+    placement: ThreadPlacement,
+    store: Option<Store>,
+    shutting_down: AtomicBool,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+}
+
+impl SearchService {
+    /// Give jobs that do not ask for a thread count `threads` workers
+    pub fn new(threads: usize) -> Self {
+        SearchService {
+            threads,
+            max_jobs: 1,
+            progress_interval: Duration::from_secs(10),
+            placement: ThreadPlacement::default(),
+            store: None,
+            shutting_down: AtomicBool::new(false),
+            jobs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Searches allowed to run at once (default 1); the rest wait in the queue
+    pub fn max_jobs(mut self, max_jobs: usize) -> Self {
+        self.max_jobs = max_jobs.max(1);
+        self
+    }
+
+    /// How often running jobs update their progress (default 10 s)
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Niceness and CPU pinning for every job's workers
+    pub fn placement(mut self, placement: ThreadPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Keep the jobs in `dir` (created if missing), loading any left by an earlier run; running
+    /// searches checkpoint every `checkpoint_interval`. Call `start` to resume the loaded queue.
+    pub fn store(mut self, dir: impl Into<PathBuf>, checkpoint_interval: Duration) -> Result<Self, VanityError> {
+        let store = Store { dir: dir.into(), checkpoint_interval };
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(&store.dir)
+            .map_err(|e| VanityError::io(format!("cannot create job store {}", store.dir.display()), e))?;
+        let path = store.jobs_path();
+        if path.exists() {
+            let text = fs::read_to_string(&path).map_err(|e| VanityError::io(format!("cannot read {}", path.display()), e))?;
+            let stored: Vec<StoredJob> = serde_json::from_str(&text)
+                .map_err(|e| VanityError::InvalidInput(format!("job store {} is not valid: {}", path.display(), e)))?;
+            let jobs = self.jobs.get_mut().unwrap();
+            jobs.extend(stored.into_iter().map(|job| (job.id, Job::restore(job))));
+        }
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    /// Record the job list in the store, if there is one
+    fn save(&self, jobs: &BTreeMap<u64, Job>) -> io::Result<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let stored: Vec<StoredJob> = jobs.iter().map(|(id, job)| job.stored(*id)).collect();
+        save_secret_json(&store.jobs_path(), &stored)
+    }
+
+    fn search(&self, request: &JobRequest, limits: SearchLimits) -> VanitySearch {
+        let mut search = VanitySearch::new()
+            .generation(request.generation)
+            .words(request.words)
+            .threads(request.threads.unwrap_or(self.threads))
+            .limits(limits)
+            .progress_interval(self.progress_interval)
+            .placement(self.placement.clone());
+        if let Some(prefix) = &request.prefix {
+            search = search.prefix(prefix.clone());
+        }
+        if let Some(suffix) = &request.suffix {
+            search = search.suffix(suffix.clone());
+        }
+        if let Some(regex) = &request.regex {
+            search = search.regex(regex.clone());
+        }
+        search
+    }
+
+    /// Check the request and queue it; it starts as soon as a slot is free
+    pub fn submit(self: &Arc<Self>, request: JobRequest) -> Result<JobView, ApiError> {
+        if request.threads == Some(0) {
+            return Err(ApiError::new(400, "threads must be at least 1"));
+        }
+        if request.max_time_secs.is_some_and(|secs| secs > MAX_TIME_SECS) {
+            return Err(ApiError::new(400, format!("max_time_secs must be at most {}", MAX_TIME_SECS)));
+        }
+        // Validates the patterns and compiles the regex
+        self.search(&request, SearchLimits::default()).config().map_err(|e| ApiError::new(400, e.to_string()))?;
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = jobs.keys().next_back().map_or(1, |last| last + 1);
+        jobs.insert(id, Job::new(request));
+        self.save(&jobs).map_err(|e| ApiError::new(500, format!("cannot record job {}: {}", id, e)))?;
+        self.start_queued(&mut jobs);
+        Ok(jobs[&id].view(id))
+    }
+
+    /// Start queued jobs while slots are free (jobs loaded from a store wait for this)
+    pub fn start(self: &Arc<Self>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.start_queued(&mut jobs);
+    }
+
+    fn start_queued(self: &Arc<Self>, jobs: &mut MutexGuard<'_, BTreeMap<u64, Job>>) {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut running = jobs.values().filter(|job| job.status == JobStatus::Running).count();
+        while running < self.max_jobs {
+            // Highest priority first, then the oldest
+            let next = jobs
+                .iter()
+                .filter(|(_, job)| job.status == JobStatus::Pending)
+                .max_by_key(|(id, job)| (job.request.priority, std::cmp::Reverse(**id)))
+                .map(|(id, _)| *id);
+            let Some(id) = next else { break };
+            let job = jobs.get_mut(&id).expect("queued job exists");
+            job.status = JobStatus::Running;
+            let service = Arc::clone(self);
+            job.thread = Some(thread::spawn(move || service.run(id)));
+            running += 1;
+        }
+        // Status changes are recorded when the jobs end; a failed write here would only lose "running"
+        let _ = self.save(jobs);
+    }
+
+    /// Run a job's search to the end and record how it went
+    fn run(self: Arc<Self>, id: u64) {
+        let (request, stop) = {
+            let jobs = self.jobs.lock().unwrap();
+            let job = &jobs[&id];
+            (job.request.clone(), Arc::clone(&job.stop))
+        };
+        let outcome = self.search_job(id, &request, stop);
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).expect("running job exists");
+        job.thread = None;
+        let mut finished = true;
+        match outcome {
+            Ok(result) => {
+                job.public_address = Some(result.public_address.clone());
+                job.result = Some(result);
+                job.status = JobStatus::Succeeded;
+            }
+            // A shutdown leaves the job queued, to resume from its checkpoint
+            Err(VanityError::Interrupted) if self.shutting_down.load(Ordering::SeqCst) => {
+                job.status = JobStatus::Pending;
+                finished = false;
+            }
+            Err(VanityError::Interrupted) => job.status = JobStatus::Cancelled,
+            Err(e) => job.status = JobStatus::Failed { reason: e.to_string() },
+        }
+        if self.save(&jobs).is_ok() && finished {
+            // The outcome is in the job list now; the checkpoint would only keep a second copy of the key
+            if let Some(store) = &self.store {
+                let _ = fs::remove_file(store.checkpoint_path(id));
+            }
+        }
+        self.start_queued(&mut jobs);
+    }
+
+    /// Search for one job, resuming its checkpoint if it has one
+    fn search_job(&self, id: u64, request: &JobRequest, stop: Arc<AtomicBool>) -> Result<SearchResult, VanityError> {
+        let mut limits = SearchLimits {
+            max_time: request.max_time_secs.map(Duration::from_secs),
+            max_attempts: request.max_attempts,
+        };
+        let config = self.search(request, limits).config()?;
+        let checkpoint = match &self.store {
+            Some(store) => {
+                let checkpoint =
+                    Checkpoint::open(store.checkpoint_path(id), store.checkpoint_interval, true, config.clone())?;
+                // Matched just before the service stopped, without reaching the job list
+                if let Some(found) = checkpoint.state.results.first() {
+                    let state = &checkpoint.state;
+                    return Ok(SearchResult {
+                        public_address: found.public_address.clone(),
+                        private_key: found.private_key.clone(),
+                        mnemonic: found.mnemonic.clone(),
+                        attempts: state.attempts,
+                        elapsed_secs: state.elapsed_secs,
+                        ..result_template(&config)
+                    });
+                }
+                // The budget covers every run of the job
+                let spent = Duration::from_secs_f64(checkpoint.state.elapsed_secs);
+                limits.max_time = limits.max_time.map(|limit| limit.saturating_sub(spent));
+                limits.max_attempts = limits.max_attempts.map(|limit| limit.saturating_sub(checkpoint.state.attempts));
+                Some(checkpoint)
+            }
+            None => None,
+        };
+        let mut search = self.search(request, limits).stop_flag(stop);
+        if let Some(checkpoint) = checkpoint {
+            search = search.checkpoint(checkpoint);
+        }
+        let hit = search.run(|event| match event {
+            SearchEvent::Progress { progress } | SearchEvent::Stopped { progress, .. } => {
+                if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+                    job.progress = Some(progress.clone());
+                }
+            }
+            _ => {}
+        })?;
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            job.progress = Some(hit.progress.clone());
+        }
+        Ok(SearchResult::new(&hit, &config))
+    }
+
+    /// Every job, oldest first
+    pub fn jobs(&self) -> Vec<JobView> {
+        self.jobs.lock().unwrap().iter().map(|(id, job)| job.view(*id)).collect()
+    }
+
+    pub fn job(&self, id: u64) -> Result<JobView, ApiError> {
+        self.jobs.lock().unwrap().get(&id).map(|job| job.view(id)).ok_or_else(|| ApiError::not_found(id))
+    }
+
+    /// Hand over the match, secrets included; the service forgets them, so this works once
+    pub fn take_result(&self, id: u64) -> Result<SearchResult, ApiError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).ok_or_else(|| ApiError::not_found(id))?;
+        let result = match (job.result.take(), &job.status) {
+            (Some(result), _) => result,
+            (None, JobStatus::Succeeded) => {
+                return Err(ApiError::new(410, format!("the result of job {} was already fetched", id)))
+            }
+            (None, status) => return Err(ApiError::new(409, format!("job {} has no result ({})", id, status))),
+        };
+        if let Err(e) = self.save(&jobs) {
+            // Keep it fetchable rather than lose track of whether the key is still on disk
+            jobs.get_mut(&id).expect("job exists").result = Some(result);
+            return Err(ApiError::new(500, format!("cannot record the fetch: {}", e)));
+        }
+        Ok(result)
+    }
+
+    /// Stop a job's search, or drop it from the queue; finished jobs are left as they are
+    pub fn cancel(&self, id: u64) -> Result<JobView, ApiError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).ok_or_else(|| ApiError::not_found(id))?;
+        match job.status {
+            JobStatus::Pending => {
+                job.status = JobStatus::Cancelled;
+                if let Some(store) = &self.store {
+                    let _ = fs::remove_file(store.checkpoint_path(id));
+                }
+            }
+            JobStatus::Running => job.stop.store(true, Ordering::SeqCst),
+            _ => {}
+        }
+        let view = jobs[&id].view(id);
+        self.save(&jobs).map_err(|e| ApiError::new(500, format!("cannot record the cancellation: {}", e)))?;
+        Ok(view)
+    }
+
+    /// Stop every running search and wait for it to checkpoint; with a store, those jobs resume on
+    /// the next start
+    pub fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let threads: Vec<JoinHandle<()>> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values_mut()
+            .filter_map(|job| {
+                job.stop.store(true, Ordering::SeqCst);
+                job.thread.take()
+            })
+            .collect();
+        for thread in threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A result for `config` without the key and accounting
+fn result_template(config: &SearchConfig) -> SearchResult {
+    SearchResult {
+        public_address: String::new(),
+        private_key: None,
+        mnemonic: None,
+        derivation_path: None,
+        generation: config.generation.clone(),
+        prefix: config.prefix.clone(),
+        suffix: config.suffix.clone(),
+        regex: config.regex.clone(),
+        attempts: 0,
+        elapsed_secs: 0.0,
+        stored_at: Vec::new(),
+        mnemonic_stored: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn request(body: &str) -> JobRequest {
+        serde_json::from_str(body).unwrap()
+    }
+
+    fn wait_for(service: &SearchService, id: u64, done: impl Fn(&JobView) -> bool) -> JobView {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        loop {
+            let view = service.job(id).unwrap();
+            if done(&view) {
+                return view;
+            }
+            assert!(Instant::now() < deadline, "job {} is stuck: {:?}", id, view);
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    fn finished(view: &JobView) -> bool {
+        !matches!(view.status, "queued" | "running")
+    }
+
+    /// A search that will not finish during a test
+    const ENDLESS: &str = r#"{"prefix": "zzzzzzzzz", "generation": "raw", "threads": 1"#;
+
+    #[test]
+    fn results_are_handed_over_once() {
+        let service = Arc::new(SearchService::new(1));
+        let job = service.submit(request(r#"{"suffix": "a", "generation": "raw"}"#)).unwrap();
+        let view = wait_for(&service, job.id, finished);
+        assert_eq!((view.status, view.result), ("succeeded", ResultState::Available));
+        let result = service.take_result(job.id).unwrap();
+        assert!(result.public_address.ends_with('a'));
+        assert_eq!(view.public_address.as_deref(), Some(result.public_address.as_str()));
+        assert!(result.private_key.is_some());
+        assert_eq!(service.take_result(job.id).unwrap_err().status, 410);
+        assert_eq!(service.job(job.id).unwrap().result, ResultState::Fetched);
+    }
+
+    #[test]
+    fn jobs_can_be_cancelled_and_exhausted() {
+        let service = Arc::new(SearchService::new(1).max_jobs(2));
+        let long = service.submit(request(&format!("{}}}", ENDLESS))).unwrap();
+        let capped = service.submit(request(&format!(r#"{}, "max_attempts": 10}}"#, ENDLESS))).unwrap();
+        assert_eq!(service.take_result(long.id).unwrap_err().status, 409);
+        service.cancel(long.id).unwrap();
+        assert_eq!(wait_for(&service, long.id, finished).status, "cancelled");
+        let capped = wait_for(&service, capped.id, finished);
+        assert_eq!(capped.status, "failed");
+        assert!(capped.reason.unwrap().contains("budget"));
+        assert_eq!(capped.progress.unwrap().attempts, 10);
+        assert_eq!(service.jobs().len(), 2);
+        assert_eq!(service.cancel(99).unwrap_err().status, 404);
+    }
+
+    #[test]
+    fn oversized_time_budget_is_refused() {
+        let service = Arc::new(SearchService::new(1));
+        let huge = request(r#"{"suffix": "a", "max_time_secs": 18446744073709551615}"#);
+        assert_eq!(service.submit(huge).unwrap_err().status, 400);
+        assert!(service.jobs().is_empty());
+    }
+
+    #[test]
+    fn queued_jobs_start_by_priority() {
+        let service = Arc::new(SearchService::new(1).max_jobs(1));
+        let first = service.submit(request(&format!("{}}}", ENDLESS))).unwrap();
+        let low = service.submit(request(&format!("{}}}", ENDLESS))).unwrap();
+        let high = service.submit(request(&format!(r#"{}, "priority": 5}}"#, ENDLESS))).unwrap();
+        assert_eq!((first.status, low.status, high.status), ("running", "queued", "queued"));
+        service.cancel(first.id).unwrap();
+        wait_for(&service, high.id, |view| view.status == "running");
+        assert_eq!(service.job(low.id).unwrap().status, "queued");
+        service.cancel(high.id).unwrap();
+        wait_for(&service, low.id, |view| view.status == "running");
+        service.shutdown();
+    }
+
+    #[test]
+    fn the_queue_survives_a_restart() {
+        let dir = std::env::temp_dir().join(format!("vanity-queue-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let open = || {
+            let service = SearchService::new(1).max_jobs(1).progress_interval(Duration::from_millis(20));
+            Arc::new(service.store(&dir, Duration::from_millis(20)).unwrap())
+        };
+        let service = open();
+        let found = service.submit(request(r#"{"suffix": "a", "generation": "raw"}"#)).unwrap();
+        wait_for(&service, found.id, finished);
+        let long = service.submit(request(&format!("{}}}", ENDLESS))).unwrap();
+        let before = wait_for(&service, long.id, |view| view.progress.is_some()).progress.unwrap().attempts;
+        let queued = service.submit(request(&format!("{}}}", ENDLESS))).unwrap();
+        service.shutdown();
+
+        let service = open();
+        let statuses: Vec<_> = service.jobs().iter().map(|view| (view.status, view.result)).collect();
+        assert_eq!(
+            statuses,
+            [("succeeded", ResultState::Available), ("queued", ResultState::None), ("queued", ResultState::None)]
+        );
+        service.start();
+        // The interrupted job runs first and carries on counting from its checkpoint
+        let resumed = wait_for(&service, long.id, |view| view.progress.as_ref().is_some_and(|p| p.session_attempts > 0));
+        assert!(resumed.progress.unwrap().attempts > before);
+        assert_eq!(service.job(queued.id).unwrap().status, "queued");
+        assert!(service.take_result(found.id).unwrap().private_key.is_some());
+        assert!(!dir.join(format!("job-{}.json", found.id)).exists());
+        service.shutdown();
+
+        let service = open();
+        assert_eq!(service.job(found.id).unwrap().result, ResultState::Fetched);
+        assert_eq!(service.take_result(found.id).unwrap_err().status, 410);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_jobs_are_rejected() {
+        let service = Arc::new(SearchService::new(1));
+        for body in [r#"{"prefix": "0OIl"}"#, r#"{"suffix": "a", "threads": 0}"#, r#"{"regex": "("}"#, "{}"] {
+            assert_eq!(service.submit(request(body)).unwrap_err().status, 400, "{}", body);
+        }
+        assert!(serde_json::from_str::<JobRequest>(r#"{"sufix": "a"}"#).is_err());
+        assert!(service.jobs().is_empty());
+    }
+}
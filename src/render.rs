@@ -0,0 +1,191 @@
+//! Job definitions for schedulers the CLI does not submit to itself, rendered as JSON
+//! (accepted by `kubectl apply -f`, `aws batch register-job-definition --cli-input-json` and
+//! `gcloud run jobs replace`).
+
+use crate::error::VanityError;
+use crate::executor::{JobSpec, ResourceLimits};
+use serde_json::{json, Map, Value};
+
+/// Memory given to AWS Batch jobs whose profile sets no limit (the field is required)
+const DEFAULT_BATCH_MEMORY_MIB: u64 = 2048;
+
+/// Which scheduler's job format to render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobTarget {
+    /// `batch/v1` Job
+    Kubernetes,
+    /// AWS Batch container job definition
+    AwsBatch,
+    /// Cloud Run job (`run.googleapis.com/v1`)
+    CloudRun,
+}
+
+/// How many copies of the search a rendered job runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanOut {
+    /// Copies running at once
+    pub parallelism: u32,
+    /// Copies to start in total, each searching until a match or its own budget runs out
+    pub completions: u32,
+}
+
+impl Default for FanOut {
+    fn default() -> Self {
+        FanOut { parallelism: 1, completions: 1 }
+    }
+}
+
+/// Render `job` (its args are the container's arguments to the `solana-vanity-seed` entrypoint)
+pub fn render_job(
+    target: JobTarget,
+    job: &JobSpec,
+    image: &str,
+    limits: &ResourceLimits,
+    fan_out: FanOut,
+) -> Result<Value, VanityError> {
+    if fan_out.parallelism == 0 || fan_out.completions < fan_out.parallelism {
+        return Err(VanityError::InvalidInput(
+            "parallelism must be at least 1 and no more than completions".to_string(),
+        ));
+    }
+    Ok(match target {
+        JobTarget::Kubernetes => kubernetes(job, image, limits, fan_out),
+        JobTarget::AwsBatch => {
+            if fan_out != FanOut::default() {
+                return Err(VanityError::InvalidInput(
+                    "AWS Batch fans out at submission (submit-job --array-properties size=N), not in the job definition"
+                        .to_string(),
+                ));
+            }
+            aws_batch(job, image, limits)
+        }
+        JobTarget::CloudRun => cloud_run(job, image, limits, fan_out),
+    })
+}
+
+/// Container resource limits in Kubernetes quantity syntax (also used by Cloud Run)
+fn quantity_limits(limits: &ResourceLimits) -> Map<String, Value> {
+    let mut out = Map::new();
+    if let Some(cpus) = limits.cpus {
+        out.insert("cpu".to_string(), json!(cpus.to_string()));
+    }
+    if let Some(memory) = limits.memory_mib {
+        out.insert("memory".to_string(), json!(format!("{}Mi", memory)));
+    }
+    if let Some(gpus) = limits.gpus {
+        out.insert("nvidia.com/gpu".to_string(), json!(gpus.to_string()));
+    }
+    out
+}
+
+fn container(job: &JobSpec, image: &str, limits: &ResourceLimits) -> Value {
+    let mut container = json!({ "name": "search", "image": image, "args": job.args });
+    let limits = quantity_limits(limits);
+    if !limits.is_empty() {
+        container["resources"] = json!({ "limits": limits });
+    }
+    container
+}
+
+/// An Indexed Job that succeeds (and stops the remaining pods) as soon as one pod finds a match;
+/// pods that run out of budget fail their index only, without retries
+fn kubernetes(job: &JobSpec, image: &str, limits: &ResourceLimits, fan_out: FanOut) -> Value {
+    json!({
+        "apiVersion": "batch/v1",
+        "kind": "Job",
+        "metadata": { "name": job.name },
+        "spec": {
+            "completionMode": "Indexed",
+            "completions": fan_out.completions,
+            "parallelism": fan_out.parallelism,
+            "backoffLimitPerIndex": 0,
+            "successPolicy": { "rules": [{ "succeededCount": 1 }] },
+            "template": {
+                "spec": {
+                    "restartPolicy": "Never",
+                    "containers": [container(job, image, limits)],
+                }
+            }
+        }
+    })
+}
+
+fn aws_batch(job: &JobSpec, image: &str, limits: &ResourceLimits) -> Value {
+    let mut requirements = vec![
+        json!({ "type": "VCPU", "value": limits.cpus.unwrap_or(1).to_string() }),
+        json!({ "type": "MEMORY", "value": limits.memory_mib.unwrap_or(DEFAULT_BATCH_MEMORY_MIB).to_string() }),
+    ];
+    if let Some(gpus) = limits.gpus {
+        requirements.push(json!({ "type": "GPU", "value": gpus.to_string() }));
+    }
+    json!({
+        "jobDefinitionName": job.name,
+        "type": "container",
+        "retryStrategy": { "attempts": 1 },
+        "containerProperties": {
+            "image": image,
+            "command": job.args,
+            "resourceRequirements": requirements,
+        }
+    })
+}
+
+/// Each task is a full search; the execution's logs carry the match from whichever task found it
+fn cloud_run(job: &JobSpec, image: &str, limits: &ResourceLimits, fan_out: FanOut) -> Value {
+    json!({
+        "apiVersion": "run.googleapis.com/v1",
+        "kind": "Job",
+        "metadata": { "name": job.name },
+        "spec": {
+            "template": {
+                "spec": {
+                    "parallelism": fan_out.parallelism,
+                    "taskCount": fan_out.completions,
+                    "template": {
+                        "spec": {
+                            "maxRetries": 0,
+                            "containers": [container(job, image, limits)],
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> JobSpec {
+        JobSpec { name: "vanity".to_string(), args: ["--threads", "8", "--suffix", "x y"].map(String::from).to_vec() }
+    }
+
+    #[test]
+    fn kubernetes_job_fans_out_until_the_first_match() {
+        let limits = ResourceLimits { cpus: Some(8), memory_mib: None, gpus: Some(1) };
+        let fan_out = FanOut { parallelism: 4, completions: 16 };
+        let manifest = render_job(JobTarget::Kubernetes, &job(), "img:1", &limits, fan_out).unwrap();
+        let spec = &manifest["spec"];
+        assert_eq!((spec["parallelism"].as_u64(), spec["completions"].as_u64()), (Some(4), Some(16)));
+        assert_eq!(spec["successPolicy"]["rules"][0]["succeededCount"], 1);
+        let container = &spec["template"]["spec"]["containers"][0];
+        assert_eq!(container["args"], json!(["--threads", "8", "--suffix", "x y"]));
+        assert_eq!(container["resources"]["limits"], json!({ "cpu": "8", "nvidia.com/gpu": "1" }));
+    }
+
+    #[test]
+    fn renders_batch_and_cloud_run_definitions() {
+        let limits = ResourceLimits::default();
+        let batch = render_job(JobTarget::AwsBatch, &job(), "img:1", &limits, FanOut::default()).unwrap();
+        assert_eq!(batch["containerProperties"]["command"][3], "x y");
+        assert_eq!(batch["containerProperties"]["resourceRequirements"][1]["value"], "2048");
+        let fan_out = FanOut { parallelism: 2, completions: 2 };
+        assert!(render_job(JobTarget::AwsBatch, &job(), "img:1", &limits, fan_out).is_err());
+        let run = render_job(JobTarget::CloudRun, &job(), "img:1", &limits, fan_out).unwrap();
+        assert_eq!(run["spec"]["template"]["spec"]["taskCount"], 2);
+        assert!(run["spec"]["template"]["spec"]["template"]["spec"]["containers"][0].get("resources").is_none());
+        let backwards = FanOut { parallelism: 3, completions: 2 };
+        assert!(render_job(JobTarget::Kubernetes, &job(), "img:1", &limits, backwards).is_err());
+    }
+}
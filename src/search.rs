@@ -0,0 +1,853 @@
+use crate::error::VanityError;
+use crate::state::{Checkpoint, FoundKey, SearchConfig};
+use crate::matcher::{AllOf, BothMatcher, Matcher, PrefixMatcher, RegexMatcher, SuffixMatcher};
+use crate::placement::ThreadPlacement;
+use crate::keysource::{Candidate, KeySource};
+use crate::sink::{deliver_all, ResultSink, SearchResult};
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use std::io;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Whether to search by prefix or suffix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchMode {
+    Prefix(String),
+    Suffix(String),
+    /// Search for both a prefix and a suffix
+    Both { prefix: String, suffix: String },
+}
+
+impl SearchMode {
+    /// Number of Base58 characters the mode pins down
+    pub fn pattern_len(&self) -> usize {
+        match self {
+            SearchMode::Prefix(p) => p.len(),
+            SearchMode::Suffix(s) => s.len(),
+            SearchMode::Both { prefix, suffix } => prefix.len() + suffix.len(),
+        }
+    }
+}
+
+/// Generation type: raw keypair, mnemonic, or token address only
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GenerationMode {
+    Raw,
+    Mnemonic,
+    Token,
+}
+
+/// Optional budget after which a search gives up (counted for this run only)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchLimits {
+    pub max_time: Option<Duration>,
+    pub max_attempts: Option<u64>,
+}
+
+/// Search accounting at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Progress {
+    /// Periodic progress updates reported so far
+    pub update: u64,
+    /// Attempts including any resumed from a state file
+    pub attempts: u64,
+    pub session_attempts: u64,
+    pub elapsed_secs: f64,
+    /// Keys per second in this session
+    pub rate: f64,
+    /// Probability that a match would have been found by now
+    pub probability: f64,
+    /// Estimated seconds until the average case; absent once it has passed
+    pub eta_secs: Option<f64>,
+}
+
+/// A matching key returned by `VanitySearch::run`
+#[derive(Debug)]
+pub struct Hit {
+    pub keypair: Keypair,
+    /// Only present for mnemonic generation
+    pub mnemonic: Option<String>,
+    /// Accounting at the moment the match was found
+    pub progress: Progress,
+}
+
+/// Notifications passed to the `VanitySearch::run` callback
+#[derive(Debug)]
+pub enum SearchEvent<'a> {
+    Started {
+        config: &'a SearchConfig,
+        resumed_attempts: u64,
+        resumed_elapsed_secs: f64,
+    },
+    /// Periodic progress while no match has been found
+    Progress { progress: &'a Progress },
+    /// The search gave up or was interrupted; `run` returns the matching error next
+    Stopped {
+        reason: &'a str,
+        progress: &'a Progress,
+    },
+    /// Writing the state file failed; the search keeps going
+    CheckpointFailed {
+        checkpoint: &'a Checkpoint,
+        error: &'a io::Error,
+    },
+    /// A result sink could not store the match; the remaining sinks still run
+    SinkFailed {
+        sink: &'a str,
+        error: &'a VanityError,
+    },
+    /// A worker joined a coordinated search (`VanitySearch::coordinate` only)
+    WorkerJoined { worker: &'a str, threads: usize },
+    /// A worker left or was turned away; the coordinated search keeps going with the others
+    WorkerLeft { worker: &'a str, reason: &'a str },
+}
+
+/// Lets an `Arc`-shared custom matcher sit inside a composite
+struct SharedMatcher(Arc<dyn Matcher>);
+
+impl Matcher for SharedMatcher {
+    fn is_match(&self, address: &str) -> bool {
+        self.0.is_match(address)
+    }
+
+    fn probability(&self) -> f64 {
+        self.0.probability()
+    }
+
+    fn describe(&self) -> String {
+        self.0.describe()
+    }
+}
+
+/// Sessions shorter than this report a rate of 0 rather than a meaningless (or infinite) one
+const MIN_RATE_SECS: f64 = 1e-3;
+
+/// Keys per second over `secs`
+fn keys_per_sec(attempts: u64, secs: f64) -> f64 {
+    if secs < MIN_RATE_SECS {
+        0.0
+    } else {
+        attempts as f64 / secs
+    }
+}
+
+/// Probability that at least one match has been seen after `attempts` tries
+pub fn probability_after(attempts: u64, avg_tries: f64) -> f64 {
+    1.0 - (-(attempts as f64) / avg_tries).exp()
+}
+
+/// Builder for an embeddable vanity search.
+///
+/// ```no_run
+/// use solana_vanity_seed::{GenerationMode, VanitySearch};
+/// use solana_sdk::signature::Signer;
+///
+/// let hit = VanitySearch::new()
+///     .prefix("Sol")
+///     .generation(GenerationMode::Raw)
+///     .threads(4)
+///     .run(|_event| {})
+///     .unwrap();
+/// println!("{}", hit.keypair.pubkey());
+/// ```
+pub struct VanitySearch {
+    pub(crate) prefix: Option<String>,
+    pub(crate) suffix: Option<String>,
+    pub(crate) regex: Option<String>,
+    pub(crate) matcher: Option<Arc<dyn Matcher>>,
+    pub(crate) generation: GenerationMode,
+    pub(crate) words: usize,
+    pub(crate) source: Option<Arc<dyn KeySource>>,
+    threads: Option<usize>,
+    pub(crate) limits: SearchLimits,
+    pub(crate) stop: Option<Arc<AtomicBool>>,
+    pub(crate) checkpoint: Option<Checkpoint>,
+    pub(crate) sinks: Vec<Box<dyn ResultSink>>,
+    rng_seed: Option<u64>,
+    pub(crate) progress_interval: Duration,
+    placement: ThreadPlacement,
+}
+
+impl Default for VanitySearch {
+    fn default() -> Self {
+        VanitySearch {
+            prefix: None,
+            suffix: None,
+            regex: None,
+            matcher: None,
+            generation: GenerationMode::Mnemonic,
+            words: 12,
+            source: None,
+            threads: None,
+            limits: SearchLimits::default(),
+            stop: None,
+            checkpoint: None,
+            sinks: Vec::new(),
+            rng_seed: None,
+            progress_interval: Duration::from_secs(10),
+            placement: ThreadPlacement::default(),
+        }
+    }
+}
+
+impl VanitySearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base58 prefix the address must start with
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Base58 suffix the address must end with
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    /// Regular expression the address must match
+    pub fn regex(mut self, regex: impl Into<String>) -> Self {
+        self.regex = Some(regex.into());
+        self
+    }
+
+    /// Custom matcher; combined with any prefix, suffix or regex (all must match)
+    pub fn matcher(mut self, matcher: impl Matcher + 'static) -> Self {
+        self.matcher = Some(Arc::new(matcher));
+        self
+    }
+
+    /// Set prefix and/or suffix from a `SearchMode`
+    pub fn mode(mut self, mode: SearchMode) -> Self {
+        let (prefix, suffix) = match mode {
+            SearchMode::Prefix(p) => (Some(p), None),
+            SearchMode::Suffix(s) => (None, Some(s)),
+            SearchMode::Both { prefix, suffix } => (Some(prefix), Some(suffix)),
+        };
+        self.prefix = prefix;
+        self.suffix = suffix;
+        self
+    }
+
+    /// How candidate keys are generated (default: mnemonic)
+    pub fn generation(mut self, generation: GenerationMode) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Mnemonic word count, 12 or 24 (default 12)
+    pub fn words(mut self, words: usize) -> Self {
+        self.words = words;
+        self
+    }
+
+    /// Use a custom key source instead of the one for the generation mode
+    pub fn key_source(mut self, source: impl KeySource + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// Worker threads (default: all logical cores)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    pub fn limits(mut self, limits: SearchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Give up after this much wall-clock time
+    pub fn max_time(mut self, max_time: Duration) -> Self {
+        self.limits.max_time = Some(max_time);
+        self
+    }
+
+    /// Give up after this many candidates
+    pub fn max_attempts(mut self, max_attempts: u64) -> Self {
+        self.limits.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Flag that stops the search when raised from another thread
+    pub fn stop_flag(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Worker niceness and CPU pinning
+    pub fn placement(mut self, placement: ThreadPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Run the workers at this niceness (higher yields more CPU to other work; Unix only)
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.placement.nice = Some(nice);
+        self
+    }
+
+    /// Pin the workers to these CPUs, round-robin (Linux only)
+    pub fn pin_cpus(mut self, cpus: Vec<usize>) -> Self {
+        self.placement.cpus = cpus;
+        self
+    }
+
+    /// Record progress to (and continue accounting from) a state file
+    pub fn checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// How often `SearchEvent::Progress` is reported (default 10 seconds)
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// INSECURE, for tests only: derive every candidate from `seed` instead of the OS-seeded RNG.
+    /// Candidate `n` always comes from ChaCha20 stream `n` of the seed, and the lowest-numbered
+    /// match wins, so the same seed and pattern yield the same key whatever the thread count.
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Hand the match to this sink when the search succeeds; sinks run in the order added
+    pub fn sink(mut self, sink: impl ResultSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Build the matcher from the prefix, suffix, regex and custom matcher (all must match)
+    pub fn build_matcher(&self) -> Result<Arc<dyn Matcher>, VanityError> {
+        if self.source.is_none() && self.generation == GenerationMode::Mnemonic && self.words != 12 && self.words != 24 {
+            return Err(VanityError::InvalidInput("Words must be 12 or 24".to_string()));
+        }
+        let mut parts: Vec<Box<dyn Matcher>> = Vec::new();
+        match (&self.prefix, &self.suffix) {
+            (Some(p), Some(s)) => parts.push(Box::new(BothMatcher::new(p.clone(), s.clone())?)),
+            (Some(p), None) => parts.push(Box::new(PrefixMatcher::new(p.clone())?)),
+            (None, Some(s)) => parts.push(Box::new(SuffixMatcher::new(s.clone())?)),
+            (None, None) => {}
+        }
+        if let Some(r) = &self.regex {
+            parts.push(Box::new(RegexMatcher::new(r)?));
+        }
+        let custom = self.matcher.clone();
+        match (parts.len(), custom) {
+            (0, None) => Err(VanityError::InvalidInput("must specify a prefix, a suffix, a regex or a matcher".to_string())),
+            (0, Some(custom)) => Ok(custom),
+            (1, None) => Ok(Arc::from(parts.pop().unwrap())),
+            (_, custom) => {
+                if let Some(custom) = custom {
+                    parts.push(Box::new(SharedMatcher(custom)));
+                }
+                Ok(Arc::new(AllOf(parts)))
+            }
+        }
+    }
+
+    /// Worker thread count this search will use
+    pub fn thread_count(&self) -> usize {
+        self.threads.unwrap_or_else(num_cpus::get)
+    }
+
+    /// The configuration recorded in state files for this search
+    pub fn config(&self) -> Result<SearchConfig, VanityError> {
+        self.build_matcher()?;
+        let (generation, words) = match &self.source {
+            Some(source) => (source.name().to_string(), 0),
+            None => {
+                // Word count only affects mnemonic generation
+                let words = if self.generation == GenerationMode::Mnemonic { self.words } else { 0 };
+                (format!("{:?}", self.generation).to_lowercase(), words)
+            }
+        };
+        Ok(SearchConfig {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.clone(),
+            regex: self.regex.clone(),
+            matcher: self.matcher.as_ref().map(|m| m.describe()),
+            generation,
+            words,
+            threads: self.thread_count(),
+        })
+    }
+
+    /// Run the search on its own worker threads until a match is found, the limits are exhausted
+    /// (`VanityError::BudgetExhausted`) or the stop flag is raised (`VanityError::Interrupted`).
+    /// The workers exist only for this call, so searches can run concurrently in one process.
+    /// `on_event` is called from the calling thread.
+    /// On a match the result is handed to each sink; sink failures are reported as
+    /// `SearchEvent::SinkFailed` and do not turn the hit into an error.
+    pub fn run<F>(self, mut on_event: F) -> Result<Hit, VanityError>
+    where
+        F: FnMut(SearchEvent<'_>),
+    {
+        let matcher = self.build_matcher()?;
+        self.placement.validate()?;
+        let threads = self.thread_count();
+        let config = self.config()?;
+        let source: Arc<dyn KeySource> = match self.source {
+            Some(source) => source,
+            None => Arc::from(self.generation.key_source(self.words)),
+        };
+        // A completed state file already holds the match; report it again instead of searching
+        if let Some(cp) = &self.checkpoint {
+            if let Some(found) = cp.state.results.first() {
+                let hit = recorded_hit(cp, found, 1.0 / matcher.probability())?;
+                deliver(self.sinks, &hit, &config, &mut on_event);
+                return Ok(hit);
+            }
+        }
+        let stop = self.stop.unwrap_or_default();
+        let settings = RunSettings {
+            threads,
+            limits: self.limits,
+            rng_seed: self.rng_seed,
+            progress_interval: self.progress_interval,
+            placement: self.placement,
+        };
+        let hit = run_search(matcher.as_ref(), source.as_ref(), &config, self.checkpoint, &settings, &stop, &mut on_event)?;
+        deliver(self.sinks, &hit, &config, &mut on_event);
+        Ok(hit)
+    }
+}
+
+/// Rebuild the match recorded in a completed state file
+fn recorded_hit(checkpoint: &Checkpoint, found: &FoundKey, avg_tries: f64) -> Result<Hit, VanityError> {
+    let path = checkpoint.path.display();
+    // Token searches keep no secret in the state file, so there is no keypair to report
+    let private_key = found.private_key.as_deref().ok_or_else(|| {
+        VanityError::InvalidInput(format!(
+            "state file {} already records {}, without its private key",
+            path, found.public_address
+        ))
+    })?;
+    let invalid = || VanityError::InvalidInput(format!("state file {} records an invalid private key", path));
+    let bytes = bs58::decode(private_key).into_vec().map_err(|_| invalid())?;
+    let keypair = Keypair::from_bytes(&bytes).map_err(|_| invalid())?;
+    if keypair.pubkey().to_string() != found.public_address {
+        return Err(invalid());
+    }
+    let state = &checkpoint.state;
+    Ok(Hit {
+        keypair,
+        mnemonic: found.mnemonic.clone(),
+        progress: Progress {
+            update: 0,
+            attempts: state.attempts,
+            session_attempts: 0,
+            elapsed_secs: state.elapsed_secs,
+            rate: 0.0,
+            probability: probability_after(state.attempts, avg_tries),
+            eta_secs: None,
+        },
+    })
+}
+
+/// Hand a match to each sink, reporting failures as `SearchEvent::SinkFailed`
+pub(crate) fn deliver(
+    mut sinks: Vec<Box<dyn ResultSink>>,
+    hit: &Hit,
+    config: &SearchConfig,
+    on_event: &mut dyn FnMut(SearchEvent<'_>),
+) {
+    if !sinks.is_empty() {
+        let mut result = SearchResult::new(hit, config);
+        for (sink, error) in deliver_all(&mut sinks, &mut result, &hit.keypair) {
+            on_event(SearchEvent::SinkFailed { sink: &sink, error: &error });
+        }
+    }
+}
+
+/// Candidate indices a worker claims at a time (rounded up to whole key-source batches); small enough
+/// that the attempt cap and a seeded search's lowest-index rule cost little extra work
+const CLAIM_SIZE: u64 = 64;
+
+/// How often the calling thread checks on the workers
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Parameters for one `run_search`
+struct RunSettings {
+    threads: usize,
+    limits: SearchLimits,
+    rng_seed: Option<u64>,
+    progress_interval: Duration,
+    placement: ThreadPlacement,
+}
+
+/// A worker's attempt counter, on its own cache line so workers don't contend
+#[derive(Default)]
+#[repr(align(128))]
+struct Counter(AtomicU64);
+
+/// Generators a worker keeps between batches, so a batch doesn't allocate them afresh
+#[derive(Default)]
+struct WorkerRngs {
+    seeded: Vec<ChaCha20Rng>,
+    unseeded: Vec<ThreadRng>,
+    /// Always empty between batches; only its allocation is kept
+    refs: Vec<&'static mut dyn RngCore>,
+}
+
+/// Empty `refs` and hand back its allocation under a new lifetime
+fn recycle<'b>(mut refs: Vec<&mut dyn RngCore>) -> Vec<&'b mut dyn RngCore> {
+    refs.clear();
+    // Collecting an empty `vec::IntoIter` in place reuses its buffer
+    refs.into_iter().map(|_| unreachable!()).collect()
+}
+
+/// State shared by the workers of one search
+struct Workers<'a> {
+    matcher: &'a dyn Matcher,
+    source: &'a dyn KeySource,
+    stop: &'a AtomicBool,
+    /// Raised if not all workers could be started
+    abort: AtomicBool,
+    deadline: Option<Instant>,
+    max_attempts: Option<u64>,
+    rng_seed: Option<u64>,
+    /// Candidates generated per `generate_batch` call
+    lanes: u64,
+    /// Candidate indices claimed per `claim`
+    claim_size: u64,
+    /// Index of this session's first candidate, counting resumed attempts
+    base_index: u64,
+    /// Next unclaimed candidate index in this session
+    next: AtomicU64,
+    /// Lowest matching index found so far (`u64::MAX` until a hit)
+    best: AtomicU64,
+    hit: Mutex<Option<Candidate>>,
+    counters: Vec<Counter>,
+}
+
+impl Workers<'_> {
+    /// Candidates generated so far in this session
+    fn attempts(&self) -> u64 {
+        self.counters.iter().map(|c| c.0.load(Ordering::Relaxed)).sum()
+    }
+
+    fn halted(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self.abort.load(Ordering::Relaxed)
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+            // Seeded searches keep going below the best index so the lowest-numbered match wins
+            || (self.rng_seed.is_none() && self.best.load(Ordering::Relaxed) != u64::MAX)
+    }
+
+    /// Claim the next range of candidate indices; `None` once the attempt cap is handed out
+    fn claim(&self) -> Option<Range<u64>> {
+        let cap = self.max_attempts.unwrap_or(u64::MAX);
+        let end = |n: u64| n.saturating_add(self.claim_size).min(cap);
+        self.next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (end(n) > n).then(|| end(n)))
+            .ok()
+            .map(|start| start..end(start))
+    }
+
+    /// Candidates `start..start + count` of this session, drawn from the worker's `rngs`
+    fn generate(&self, start: u64, count: u64, rngs: &mut WorkerRngs) -> Vec<Candidate> {
+        let first = self.base_index + start;
+        let count = count as usize;
+        let mut refs = recycle(std::mem::take(&mut rngs.refs));
+        match self.rng_seed {
+            // Candidate n always comes from stream n, however candidates are grouped
+            Some(seed) => {
+                while rngs.seeded.len() < count {
+                    rngs.seeded.push(ChaCha20Rng::seed_from_u64(seed));
+                }
+                for (rng, index) in rngs.seeded.iter_mut().zip(first..first + count as u64) {
+                    rng.set_stream(index);
+                    rng.set_word_pos(0);
+                    refs.push(rng);
+                }
+            }
+            None => {
+                while rngs.unseeded.len() < count {
+                    rngs.unseeded.push(thread_rng());
+                }
+                refs.extend(rngs.unseeded[..count].iter_mut().map(|r| r as &mut dyn RngCore));
+            }
+        }
+        let candidates = self.source.generate_batch(&mut refs);
+        rngs.refs = recycle(refs);
+        candidates
+    }
+
+    /// Keep the match if it is the lowest-numbered so far
+    fn record(&self, index: u64, candidate: Candidate) {
+        let mut hit = self.hit.lock().unwrap();
+        if index < self.best.load(Ordering::Relaxed) {
+            self.best.store(index, Ordering::Relaxed);
+            *hit = Some(candidate);
+        }
+    }
+
+    /// Worker loop: claim ranges and test candidates until halted or out of attempts
+    fn work(&self, counter: &Counter) {
+        let mut rngs = WorkerRngs::default();
+        while let Some(range) = self.claim() {
+            let mut start = range.start;
+            while start < range.end {
+                if self.halted() || start >= self.best.load(Ordering::Relaxed) {
+                    return;
+                }
+                let count = self.lanes.min(range.end - start);
+                let candidates = self.generate(start, count, &mut rngs);
+                counter.0.fetch_add(count, Ordering::Relaxed);
+                let found = candidates
+                    .into_iter()
+                    .enumerate()
+                    .find(|(_, candidate)| self.matcher.is_match(&candidate.keypair.pubkey().to_string()));
+                if let Some((i, candidate)) = found {
+                    // The rest of this range has higher indices
+                    self.record(start + i as u64, candidate);
+                    break;
+                }
+                start += count;
+            }
+        }
+    }
+}
+
+/// Runs the brute-force search on long-lived worker threads while the calling thread reports
+/// progress every `progress_interval` and records checkpoints.
+/// When a checkpoint is given, progress is recorded to its state file and accounting continues from it.
+/// Workers stop at the next candidate once `stop` is raised or the limits are exhausted,
+/// which is reported as `VanityError::Interrupted` or `VanityError::BudgetExhausted`.
+fn run_search(
+    matcher: &dyn Matcher,
+    source: &dyn KeySource,
+    config: &SearchConfig,
+    mut checkpoint: Option<Checkpoint>,
+    settings: &RunSettings,
+    stop: &AtomicBool,
+    on_event: &mut dyn FnMut(SearchEvent<'_>),
+) -> Result<Hit, VanityError> {
+    // Attempts and elapsed time carried over from a resumed state file
+    let (prior_attempts, prior_secs) = checkpoint
+        .as_ref()
+        .map(|c| (c.state.attempts, c.state.elapsed_secs))
+        .unwrap_or((0, 0.0));
+    let avg_tries = 1.0 / matcher.probability();
+    let total_start = Instant::now();
+    let lanes = source.batch_size().max(1) as u64;
+    let workers = Workers {
+        matcher,
+        source,
+        stop,
+        abort: AtomicBool::new(false),
+        // A budget too far out to represent is no deadline at all
+        deadline: settings.limits.max_time.and_then(|d| total_start.checked_add(d)),
+        max_attempts: settings.limits.max_attempts,
+        rng_seed: settings.rng_seed,
+        lanes,
+        claim_size: CLAIM_SIZE.div_ceil(lanes) * lanes,
+        base_index: prior_attempts,
+        next: AtomicU64::new(0),
+        best: AtomicU64::new(u64::MAX),
+        hit: Mutex::new(None),
+        counters: (0..settings.threads.max(1)).map(|_| Counter::default()).collect(),
+    };
+    // Accounting across this session and any resumed one
+    let snapshot = |update: u64| {
+        let session_secs = total_start.elapsed().as_secs_f64();
+        let session_attempts = workers.attempts();
+        let total_attempts = prior_attempts + session_attempts;
+        // ETA to the average case, using this session's rate and all attempts so far
+        let rate = keys_per_sec(session_attempts, session_secs);
+        let remaining = avg_tries - total_attempts as f64;
+        Progress {
+            update,
+            attempts: total_attempts,
+            session_attempts,
+            elapsed_secs: prior_secs + session_secs,
+            rate,
+            probability: probability_after(total_attempts, avg_tries),
+            eta_secs: if remaining > 0.0 && rate > 0.0 { Some(remaining / rate) } else { None },
+        }
+    };
+    on_event(SearchEvent::Started {
+        config,
+        resumed_attempts: prior_attempts,
+        resumed_elapsed_secs: prior_secs,
+    });
+    let updates = thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for (i, counter) in workers.counters.iter().enumerate() {
+            let workers = &workers;
+            let placement = &settings.placement;
+            let spawned = thread::Builder::new().name(format!("vanity-worker-{}", i)).spawn_scoped(scope, move || {
+                if let Err(e) = placement.apply(i) {
+                    workers.abort.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                workers.work(counter);
+                Ok(())
+            });
+            match spawned {
+                Ok(handle) => handles.push(handle),
+                Err(e) => {
+                    workers.abort.store(true, Ordering::Relaxed);
+                    return Err(VanityError::io("cannot start worker threads", e));
+                }
+            }
+        }
+        let mut updates = 0;
+        let mut last_report = Instant::now();
+        let mut last_checkpoint = Instant::now();
+        while !handles.iter().all(|h| h.is_finished()) {
+            thread::sleep(POLL_INTERVAL);
+            // Record progress so a preempted run can be resumed
+            if let Some(cp) = checkpoint.as_mut() {
+                if last_checkpoint.elapsed() >= cp.interval {
+                    let progress = snapshot(updates);
+                    if let Err(error) = cp.save(progress.attempts, progress.elapsed_secs, None) {
+                        on_event(SearchEvent::CheckpointFailed { checkpoint: cp, error: &error });
+                    }
+                    last_checkpoint = Instant::now();
+                }
+            }
+            if last_report.elapsed() >= settings.progress_interval {
+                updates += 1;
+                on_event(SearchEvent::Progress { progress: &snapshot(updates) });
+                last_report = Instant::now();
+            }
+        }
+        for handle in handles {
+            handle.join().expect("search worker panicked")?;
+        }
+        Ok(updates)
+    })?;
+    let progress = snapshot(updates);
+    match workers.hit.into_inner().unwrap() {
+        Some(Candidate { keypair, mnemonic }) => {
+            let mnemonic = mnemonic.map(|m| m.to_string());
+            if let Some(cp) = checkpoint.as_mut() {
+                let found = FoundKey {
+                    public_address: keypair.pubkey().to_string(),
+                    private_key: if source.reveals_secret() {
+                        Some(bs58::encode(&keypair.to_bytes()).into_string())
+                    } else {
+                        None
+                    },
+                    mnemonic: mnemonic.clone(),
+                };
+                if let Err(error) = cp.save(progress.attempts, progress.elapsed_secs, Some(found)) {
+                    on_event(SearchEvent::CheckpointFailed { checkpoint: cp, error: &error });
+                }
+            }
+            Ok(Hit { keypair, mnemonic, progress })
+        }
+        None => {
+            let (err, reason) = if stop.load(Ordering::Relaxed) {
+                (VanityError::Interrupted, "interrupted")
+            } else {
+                (VanityError::BudgetExhausted, "budget exhausted")
+            };
+            // Flush progress so the search can be resumed where it stopped
+            if let Some(cp) = checkpoint.as_mut() {
+                if let Err(error) = cp.save(progress.attempts, progress.elapsed_secs, None) {
+                    on_event(SearchEvent::CheckpointFailed { checkpoint: cp, error: &error });
+                }
+            }
+            on_event(SearchEvent::Stopped { reason, progress: &progress });
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget_run(search: VanitySearch) -> (u64, Result<Hit, VanityError>) {
+        let mut attempts = 0;
+        let result = search.run(|event| {
+            if let SearchEvent::Stopped { progress, .. } = event {
+                attempts = progress.attempts;
+            }
+        });
+        (attempts, result)
+    }
+
+    #[test]
+    fn attempt_cap_is_exact_across_threads_and_lanes() {
+        for (generation, cap) in [(GenerationMode::Raw, 1_000), (GenerationMode::Mnemonic, 13)] {
+            let search = VanitySearch::new()
+                .generation(generation)
+                .prefix("zzzzzzzz")
+                .threads(3)
+                .limits(SearchLimits { max_time: None, max_attempts: Some(cap) });
+            let (attempts, result) = budget_run(search);
+            assert!(matches!(result, Err(VanityError::BudgetExhausted)));
+            assert_eq!(attempts, cap);
+        }
+    }
+
+    #[test]
+    fn unrepresentable_time_budget_means_no_deadline() {
+        let search = VanitySearch::new()
+            .generation(GenerationMode::Raw)
+            .prefix("zzzzzzzz")
+            .threads(1)
+            .limits(SearchLimits { max_time: Some(Duration::MAX), max_attempts: Some(100) });
+        let (attempts, result) = budget_run(search);
+        assert!(matches!(result, Err(VanityError::BudgetExhausted)));
+        assert_eq!(attempts, 100);
+    }
+
+    #[test]
+    fn seeded_hit_does_not_depend_on_thread_count() {
+        let find = |threads| {
+            VanitySearch::new()
+                .generation(GenerationMode::Raw)
+                .suffix("ab")
+                .threads(threads)
+                .rng_seed(11)
+                .run(|_| {})
+                .unwrap()
+                .keypair
+                .pubkey()
+        };
+        assert_eq!(find(1), find(4));
+    }
+
+    #[test]
+    fn stop_flag_interrupts_workers() {
+        let stop = Arc::new(AtomicBool::new(true));
+        let result = VanitySearch::new()
+            .generation(GenerationMode::Raw)
+            .prefix("zzzzzzzz")
+            .threads(2)
+            .stop_flag(stop)
+            .run(|event| {
+                // Stopped at once: no rate yet, and nothing non-finite for the JSON output
+                if let SearchEvent::Stopped { progress, .. } = event {
+                    assert!(progress.rate.is_finite());
+                    assert!(progress.eta_secs.is_none_or(f64::is_finite));
+                }
+            });
+        assert!(matches!(result, Err(VanityError::Interrupted)));
+    }
+
+    #[test]
+    fn rate_is_zero_until_time_has_passed() {
+        assert_eq!(keys_per_sec(0, 0.0), 0.0);
+        assert_eq!(keys_per_sec(500, 0.0), 0.0);
+        assert_eq!(keys_per_sec(500, 2.0), 250.0);
+    }
+}
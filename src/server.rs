@@ -0,0 +1,220 @@
+//! Search service: vanity searches submitted, polled, collected and cancelled over a small HTTP/JSON
+//! API (`solana-vanity-seed serve`), so a bot or web front-end can drive the same search as the CLI.
+//!
+//! | Request | Answer |
+//! |---------|--------|
+//! | `POST /jobs` with a [`JobRequest`] | `201` and the new job |
+//! | `GET /jobs` | every job, oldest first |
+//! | `GET /jobs/ID` | the job's status and progress |
+//! | `GET /jobs/ID/result` | the match with its secrets, once; `410` afterwards |
+//! | `DELETE /jobs/ID` | `202`; the search stops shortly after |
+//!
+//! Results carry private keys and the API is plain HTTP, so it should only be reachable from this
+//! machine or through a tunnel; with a token, every request needs `Authorization: Bearer TOKEN`.
+//! Browsers are kept out: a request with an `Origin` header, or whose `Host` is not this machine's
+//! address or `localhost`, is refused, so a web page cannot reach the API through DNS rebinding.
+
+use crate::error::VanityError;
+use crate::queue::{ApiError, JobRequest, SearchService};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Longest request accepted (request line, headers and body)
+const MAX_REQUEST: u64 = 64 * 1024;
+
+/// Time a client gets to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the server checks for connections and the stop flag
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Answer API requests on `listener` until `stop` is raised. With a `token`, requests without
+/// `Authorization: Bearer <token>` are refused.
+pub fn serve(
+    service: Arc<SearchService>,
+    listener: TcpListener,
+    token: Option<String>,
+    stop: &AtomicBool,
+) -> Result<(), VanityError> {
+    listener.set_nonblocking(true).map_err(|e| VanityError::io("cannot listen for API requests", e))?;
+    let token: Option<Arc<str>> = token.map(Arc::from);
+    while !stop.load(Ordering::SeqCst) {
+        // Accept errors (e.g. a client resetting) only affect that client
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let (service, token) = (Arc::clone(&service), token.clone());
+                thread::spawn(move || {
+                    let _ = stream.set_nonblocking(false).and_then(|_| handle(stream, &service, token.as_deref()));
+                });
+            }
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+    Ok(())
+}
+
+/// A parsed HTTP request
+struct Request {
+    method: String,
+    path: String,
+    host: Option<String>,
+    /// Sent by browsers only
+    origin: Option<String>,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, ApiError> {
+    let bad = |e: io::Error| ApiError::new(400, format!("cannot read request: {}", e));
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST));
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(bad)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path), Some(_version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(ApiError::new(400, "malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+    let (mut length, mut host, mut origin, mut authorization) = (0, None, None, None);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(bad)? == 0 {
+            return Err(ApiError::new(413, "request too large or cut short"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(ApiError::new(400, "malformed header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<u64>().map_err(|_| ApiError::new(400, "bad Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("host") {
+            host = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("origin") {
+            origin = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        }
+    }
+    if length > MAX_REQUEST {
+        return Err(ApiError::new(413, "request too large"));
+    }
+    let mut body = vec![0; length as usize];
+    reader.read_exact(&mut body).map_err(|_| ApiError::new(413, "request too large or cut short"))?;
+    Ok(Request { method, path, host, origin, authorization, body })
+}
+
+/// Whether a `Host` header names this machine: the address the client connected to, or loopback
+fn host_allowed(host: &str, local: IpAddr) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip == local || ip == IpAddr::V4(Ipv4Addr::LOCALHOST) || ip == IpAddr::V6(Ipv6Addr::LOCALHOST))
+}
+
+/// Compare without revealing how much of the token matched
+fn token_matches(offered: &str, token: &str) -> bool {
+    offered.len() == token.len() && offered.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn handle(mut stream: TcpStream, service: &Arc<SearchService>, token: Option<&str>) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let local = stream.local_addr()?.ip();
+    let answer = read_request(&stream).and_then(|request| {
+        if request.origin.is_some() {
+            return Err(ApiError::new(403, "browser requests are not accepted"));
+        }
+        if !request.host.as_deref().is_some_and(|host| host_allowed(host, local)) {
+            return Err(ApiError::new(403, "Host must be this machine's address or localhost"));
+        }
+        if let Some(token) = token {
+            let offered = request.authorization.as_deref().and_then(|a| a.strip_prefix("Bearer ")).unwrap_or("");
+            if !token_matches(offered, token) {
+                return Err(ApiError::new(401, "missing or wrong API token"));
+            }
+        }
+        route(service, &request)
+    });
+    let (status, body) = match answer {
+        Ok(answer) => answer,
+        Err(e) => (e.status, json!({ "error": e.message })),
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        410 => "Gone",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("API responses serialize")
+}
+
+fn route(service: &Arc<SearchService>, request: &Request) -> Result<(u16, Value), ApiError> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let id = |segment: &str| segment.parse::<u64>().map_err(|_| ApiError::new(404, format!("no job {:?}", segment)));
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["jobs"]) => Ok((200, json!({ "jobs": service.jobs() }))),
+        ("POST", ["jobs"]) => {
+            let job: JobRequest = serde_json::from_slice(&request.body)
+                .map_err(|e| ApiError::new(400, format!("invalid job: {}", e)))?;
+            Ok((201, to_json(&service.submit(job)?)))
+        }
+        ("GET", ["jobs", job]) => Ok((200, to_json(&service.job(id(job)?)?))),
+        ("DELETE", ["jobs", job]) => Ok((202, to_json(&service.cancel(id(job)?)?))),
+        ("GET", ["jobs", job, "result"]) => Ok((200, to_json(&service.take_result(id(job)?)?))),
+        (_, ["jobs"] | ["jobs", _] | ["jobs", _, "result"]) => Err(ApiError::new(405, "method not allowed")),
+        _ => Err(ApiError::new(404, format!("no such endpoint {}", path))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_must_name_this_machine() {
+        let local: IpAddr = "10.0.0.5".parse().unwrap();
+        for host in ["localhost", "LOCALHOST:8080", "127.0.0.1:8080", "[::1]:8080", "10.0.0.5", "10.0.0.5:8080"] {
+            assert!(host_allowed(host, local), "{}", host);
+        }
+        for host in ["", "evil.example", "evil.example:8080", "localhost.evil.example", "10.0.0.6:8080", "[::2]"] {
+            assert!(!host_allowed(host, local), "{}", host);
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use crate::error::VanityError;
+use crate::format_duration;
+use crate::search::{Hit, Progress};
+use crate::state::SearchConfig;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hmac::Hmac;
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How results and progress are written to the terminal
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// One JSON document with the result (or stop summary) on stdout
+    Json,
+    /// Stream start, progress and result events as JSON lines on stdout
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!("Invalid format '{}' (use text, json or jsonl)", s)),
+        }
+    }
+}
+
+/// A matching key with everything needed to use it; sinks decide how much of it to keep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub public_address: String,
+    /// Omitted from terminal output for token searches and once a sink has stored the secret
+    pub private_key: Option<String>,
+    /// Only present for mnemonic generation
+    pub mnemonic: Option<String>,
+    /// None when the key comes straight from the BIP-39 seed (solana-keygen's default)
+    pub derivation_path: Option<String>,
+    pub generation: String,
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub regex: Option<String>,
+    pub attempts: u64,
+    pub elapsed_secs: f64,
+    /// Where earlier sinks stored the secret (files, endpoints)
+    #[serde(default)]
+    pub stored_at: Vec<String>,
+    /// Whether one of those sinks also kept the mnemonic
+    #[serde(skip)]
+    pub mnemonic_stored: bool,
+}
+
+impl SearchResult {
+    pub fn new(hit: &Hit, config: &SearchConfig) -> Self {
+        SearchResult {
+            public_address: hit.keypair.pubkey().to_string(),
+            private_key: Some(bs58::encode(&hit.keypair.to_bytes()).into_string()),
+            mnemonic: hit.mnemonic.clone(),
+            derivation_path: None,
+            generation: config.generation.clone(),
+            prefix: config.prefix.clone(),
+            suffix: config.suffix.clone(),
+            regex: config.regex.clone(),
+            attempts: hit.progress.attempts,
+            elapsed_secs: hit.progress.elapsed_secs,
+            stored_at: Vec::new(),
+            mnemonic_stored: false,
+        }
+    }
+
+    fn is_token(&self) -> bool {
+        self.generation == "token"
+    }
+}
+
+/// Structured output records, tagged by `event`
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Start {
+        #[serde(flatten)]
+        config: &'a SearchConfig,
+        resumed_attempts: u64,
+        resumed_elapsed_secs: f64,
+    },
+    Progress(&'a Progress),
+    Result(&'a SearchResult),
+    Stopped {
+        reason: &'a str,
+        #[serde(flatten)]
+        progress: &'a Progress,
+    },
+    WorkerJoined {
+        worker: &'a str,
+        threads: usize,
+    },
+    WorkerLeft {
+        worker: &'a str,
+        reason: &'a str,
+    },
+}
+
+impl Event<'_> {
+    /// Print the event as one JSON line on stdout
+    pub fn emit(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("Warning: failed to encode output: {}", e),
+        }
+    }
+}
+
+/// Where a sink put the secret
+#[derive(Debug, Clone)]
+pub struct StoredSecret {
+    pub location: String,
+    /// Whether the mnemonic was kept along with the private key
+    pub includes_mnemonic: bool,
+}
+
+/// A destination for found keys
+pub trait ResultSink: Send {
+    /// Short name used in error messages
+    fn name(&self) -> String;
+
+    /// Store or display `result`. Returns where the secret was stored, if this sink keeps it.
+    fn deliver(&mut self, result: &SearchResult, keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError>;
+}
+
+impl<S: ResultSink + ?Sized> ResultSink for Box<S> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn deliver(&mut self, result: &SearchResult, keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        (**self).deliver(result, keypair)
+    }
+}
+
+/// Hand a result to each sink in order. Later sinks see where earlier ones stored the secret,
+/// so a terminal sink placed last only shows secrets that were not stored anywhere.
+/// Returns the sinks that failed.
+pub fn deliver_all(
+    sinks: &mut [Box<dyn ResultSink>],
+    result: &mut SearchResult,
+    keypair: &Keypair,
+) -> Vec<(String, VanityError)> {
+    let mut failures = Vec::new();
+    for sink in sinks.iter_mut() {
+        match sink.deliver(result, keypair) {
+            Ok(Some(stored)) => {
+                result.stored_at.push(stored.location);
+                result.mnemonic_stored |= stored.includes_mnemonic;
+            }
+            Ok(None) => {}
+            Err(e) => failures.push((sink.name(), e)),
+        }
+    }
+    failures
+}
+
+/// Create `path` for writing secrets: refuses to overwrite and is readable by the owner only
+pub(crate) fn create_secret_file(path: &Path) -> io::Result<fs::File> {
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    opts.open(path)
+}
+
+/// Prints results to stdout in the selected format
+pub struct TerminalSink {
+    pub format: OutputFormat,
+    /// Include total run time in text results
+    pub time: bool,
+}
+
+impl ResultSink for TerminalSink {
+    fn name(&self) -> String {
+        "terminal".to_string()
+    }
+
+    fn deliver(&mut self, result: &SearchResult, _keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        // Token searches only show the address; secrets already stored elsewhere are not repeated
+        let mut shown = result.clone();
+        if result.is_token() || !result.stored_at.is_empty() {
+            shown.private_key = None;
+        }
+        if result.mnemonic_stored {
+            shown.mnemonic = None;
+        }
+        match self.format {
+            OutputFormat::Json | OutputFormat::Jsonl => Event::Result(&shown).emit(),
+            OutputFormat::Text if result.is_token() => {
+                println!("Token Address: {}", shown.public_address);
+                for location in &shown.stored_at {
+                    println!("Mint keypair stored at: {}", location);
+                }
+                if self.time {
+                    println!("⏱ Total run time: {}", format_duration(shown.elapsed_secs));
+                }
+                println!("⚠️  Record your token address now, then delete this message for safety.");
+            }
+            OutputFormat::Text => {
+                if let Some(mnemonic) = &shown.mnemonic {
+                    println!("Mnemonic: {}", mnemonic);
+                }
+                println!("Public Address: {}", shown.public_address);
+                if let Some(private_key) = &shown.private_key {
+                    println!("Base58 Private Key: {}", private_key);
+                }
+                for location in &shown.stored_at {
+                    println!("Secret stored at: {}", location);
+                }
+                if self.time {
+                    println!("⏱ Total run time: {}", format_duration(shown.elapsed_secs));
+                }
+                println!("⚠️  Record your address and private key now, then delete for safety.");
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Writes the keypair as a solana-keygen JSON file (64-byte array)
+pub struct KeypairFileSink {
+    pub path: PathBuf,
+}
+
+impl ResultSink for KeypairFileSink {
+    fn name(&self) -> String {
+        format!("keypair file {}", self.path.display())
+    }
+
+    fn deliver(&mut self, _result: &SearchResult, keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        let err = |e| VanityError::io(format!("cannot write keypair file {}", self.path.display()), e);
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).map_err(|e| err(io::Error::other(e)))?;
+        let mut file = create_secret_file(&self.path).map_err(err)?;
+        file.write_all(json.as_bytes()).map_err(err)?;
+        Ok(Some(StoredSecret { location: self.path.display().to_string(), includes_mnemonic: false }))
+    }
+}
+
+/// Appends each result (with secrets) as one JSON line
+pub struct JsonLinesSink {
+    pub path: PathBuf,
+}
+
+impl ResultSink for JsonLinesSink {
+    fn name(&self) -> String {
+        format!("JSON lines file {}", self.path.display())
+    }
+
+    fn deliver(&mut self, result: &SearchResult, _keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        let err = |e| VanityError::io(format!("cannot append to {}", self.path.display()), e);
+        let line = serde_json::to_string(result).map_err(|e| err(io::Error::other(e)))?;
+        let mut opts = fs::OpenOptions::new();
+        opts.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            opts.mode(0o600);
+        }
+        let mut file = opts.open(&self.path).map_err(err)?;
+        writeln!(file, "{}", line).map_err(err)?;
+        Ok(Some(StoredSecret { location: self.path.display().to_string(), includes_mnemonic: true }))
+    }
+}
+
+/// PBKDF2 rounds for deriving the encryption key from a passphrase
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+/// On-disk format of an encrypted result
+#[derive(Serialize, Deserialize)]
+struct EncryptedEnvelope {
+    /// Always "aes-256-gcm/pbkdf2-sha256"
+    scheme: String,
+    rounds: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, rounds, &mut key);
+    key
+}
+
+/// Writes the result (with secrets) encrypted with AES-256-GCM under a passphrase-derived key
+pub struct EncryptedFileSink {
+    pub path: PathBuf,
+    pub passphrase: String,
+}
+
+impl ResultSink for EncryptedFileSink {
+    fn name(&self) -> String {
+        format!("encrypted file {}", self.path.display())
+    }
+
+    fn deliver(&mut self, result: &SearchResult, _keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        let err = |e| VanityError::io(format!("cannot write encrypted file {}", self.path.display()), e);
+        let plaintext = serde_json::to_vec(result).map_err(|e| err(io::Error::other(e)))?;
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut salt);
+        thread_rng().fill_bytes(&mut nonce);
+        let key = derive_key(&self.passphrase, &salt, PBKDF2_ROUNDS);
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| err(io::Error::other(e.to_string())))?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|e| err(io::Error::other(e.to_string())))?;
+        let envelope = EncryptedEnvelope {
+            scheme: "aes-256-gcm/pbkdf2-sha256".to_string(),
+            rounds: PBKDF2_ROUNDS,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        let data = serde_json::to_string_pretty(&envelope).map_err(|e| err(io::Error::other(e)))?;
+        let mut file = create_secret_file(&self.path).map_err(err)?;
+        file.write_all(data.as_bytes()).map_err(err)?;
+        Ok(Some(StoredSecret { location: self.path.display().to_string(), includes_mnemonic: true }))
+    }
+}
+
+/// Read back a file written by `EncryptedFileSink`
+pub fn decrypt_result_file(path: &Path, passphrase: &str) -> Result<SearchResult, VanityError> {
+    let data = fs::read_to_string(path).map_err(|e| VanityError::io(format!("cannot read {}", path.display()), e))?;
+    let invalid = |what: &str| VanityError::InvalidInput(format!("{} is not a valid encrypted result: {}", path.display(), what));
+    let envelope: EncryptedEnvelope = serde_json::from_str(&data).map_err(|e| invalid(&e.to_string()))?;
+    let salt = hex::decode(&envelope.salt).map_err(|_| invalid("bad salt"))?;
+    let nonce = hex::decode(&envelope.nonce).map_err(|_| invalid("bad nonce"))?;
+    let ciphertext = hex::decode(&envelope.ciphertext).map_err(|_| invalid("bad ciphertext"))?;
+    if nonce.len() != 12 {
+        return Err(invalid("bad nonce"));
+    }
+    let key = derive_key(passphrase, &salt, envelope.rounds);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| invalid("bad key"))?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| VanityError::InvalidInput("wrong passphrase or corrupted file".to_string()))?;
+    serde_json::from_slice(&plaintext).map_err(|e| invalid(&e.to_string()))
+}
+
+/// POSTs the result (with secrets) as JSON to an HTTP endpoint on this machine
+pub struct WebhookSink {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl WebhookSink {
+    /// Accepts `http://localhost[:port]/path` (or 127.0.0.1 / [::1]); secrets never leave the machine
+    pub fn new(url: &str) -> Result<Self, VanityError> {
+        let invalid = |why: &str| VanityError::InvalidInput(format!("invalid webhook URL {:?}: {}", url, why));
+        let rest = url.strip_prefix("http://").ok_or_else(|| invalid("only http:// to a local endpoint is supported"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((h, p)) if !h.ends_with(':') => (h, p.parse().map_err(|_| invalid("bad port"))?),
+            _ => (authority, 80),
+        };
+        if !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+            return Err(invalid("host must be localhost, 127.0.0.1 or [::1]"));
+        }
+        Ok(WebhookSink { host: host.to_string(), port, path: path.to_string() })
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+impl ResultSink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook {}", self.url())
+    }
+
+    fn deliver(&mut self, result: &SearchResult, _keypair: &Keypair) -> Result<Option<StoredSecret>, VanityError> {
+        let err = |e| VanityError::io(format!("webhook {} failed", self.url()), e);
+        let body = serde_json::to_string(result).map_err(|e| err(io::Error::other(e)))?;
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        let addr = (host, self.port)
+            .to_socket_addrs()
+            .map_err(err)?
+            .next()
+            .ok_or_else(|| err(io::Error::new(io::ErrorKind::NotFound, "no address")))?;
+        let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(10)).map_err(err)?;
+        stream.set_read_timeout(Some(Duration::from_secs(10))).map_err(err)?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            self.port,
+            body.len(),
+            body
+        )
+        .map_err(err)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(err)?;
+        let status = response.split_whitespace().nth(1).unwrap_or("");
+        if !status.starts_with('2') {
+            return Err(err(io::Error::other(format!("endpoint answered {:?}", status))));
+        }
+        Ok(Some(StoredSecret { location: self.url(), includes_mnemonic: true }))
+    }
+}
@@ -0,0 +1,482 @@
+//! Running a search on your own machines over SSH. Each host runs `solana-vanity-seed --format jsonl`
+//! in its own SSH session; the executor reads the hosts' progress events as they arrive and stops
+//! every other host as soon as one reports a match.
+//!
+//! Hosts are plain `ssh` destinations (`host`, `user@host` or a `~/.ssh/config` alias, which is also
+//! where ports and keys belong). Sessions run with `BatchMode=yes`, so keys must work without a prompt.
+
+use crate::error::VanityError;
+use crate::executor::{shell_join, shell_quote, Executor, JobHandle, JobProgress, JobSpec, JobStatus};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Directory (relative to the remote home) that uploaded binaries are kept in
+const INSTALL_DIR: &str = ".cache/solana-vanity-seed";
+
+/// One machine of an SSH executor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    /// `ssh` destination
+    pub destination: String,
+    /// Search threads on this host; unset hosts share the job's `--threads` (or use all their cores)
+    pub threads: Option<usize>,
+}
+
+impl FromStr for SshHost {
+    type Err = String;
+
+    /// `DEST` or `DEST=THREADS`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (destination, threads) = match s.rsplit_once('=') {
+            Some((destination, threads)) => {
+                let threads = threads.trim().parse::<usize>().ok().filter(|&n| n > 0);
+                (destination, Some(threads.ok_or_else(|| format!("Invalid thread count in '{}'", s))?))
+            }
+            None => (s, None),
+        };
+        let destination = destination.trim();
+        if destination.is_empty() || destination.starts_with('-') || destination.contains(char::is_whitespace) {
+            return Err(format!("Invalid SSH host '{}'", s));
+        }
+        Ok(SshHost { destination: destination.to_string(), threads })
+    }
+}
+
+impl fmt::Display for SshHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.threads {
+            Some(threads) => write!(f, "{}={}", self.destination, threads),
+            None => write!(f, "{}", self.destination),
+        }
+    }
+}
+
+/// Where the hosts get `solana-vanity-seed` from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteBinary {
+    /// Already installed: a program name on the remote `PATH`, or a path
+    Installed(String),
+    /// Copied from this machine on first use; `remote` names it by content so hosts keep one copy per build
+    Upload { local: PathBuf, remote: String },
+}
+
+impl RemoteBinary {
+    /// Upload `local` (which must suit the hosts' OS and architecture)
+    pub fn upload(local: impl Into<PathBuf>) -> Result<Self, VanityError> {
+        let local = local.into();
+        let bytes = fs::read(&local).map_err(|e| VanityError::io(format!("cannot read {}", local.display()), e))?;
+        let digest = hex::encode(&Sha256::digest(&bytes)[..8]);
+        Ok(RemoteBinary::Upload { local, remote: format!("{}/solana-vanity-seed-{}", INSTALL_DIR, digest) })
+    }
+
+    fn program(&self) -> &str {
+        match self {
+            RemoteBinary::Installed(program) => program,
+            RemoteBinary::Upload { remote, .. } => remote,
+        }
+    }
+}
+
+/// Runs the search on every host at once, each with its share of the threads
+pub struct SshExecutor {
+    hosts: Vec<SshHost>,
+    binary: RemoteBinary,
+    jobs: HashMap<String, Arc<SshJob>>,
+    submitted: usize,
+}
+
+struct SshJob {
+    sessions: Vec<Session>,
+    cancelled: AtomicBool,
+}
+
+struct Session {
+    destination: String,
+    child: Mutex<Child>,
+    state: Mutex<SessionState>,
+}
+
+#[derive(Default)]
+struct SessionState {
+    /// Remote process id, printed by the session before the search starts
+    pid: Option<u32>,
+    /// Output lines other than progress events
+    output: Vec<String>,
+    /// Latest (attempts, rate)
+    progress: Option<(u64, f64)>,
+}
+
+impl SshExecutor {
+    pub fn new(hosts: Vec<SshHost>, binary: RemoteBinary) -> Self {
+        SshExecutor { hosts, binary, jobs: HashMap::new(), submitted: 0 }
+    }
+
+    /// The search command line for each host
+    fn host_commands(&self, job: &JobSpec) -> Vec<(&SshHost, String)> {
+        let (threads, args) = split_args(&job.args);
+        let shares = partition(threads, &self.hosts);
+        self.hosts
+            .iter()
+            .zip(shares)
+            .map(|(host, threads)| {
+                let mut argv = vec![self.binary.program().to_string()];
+                if let Some(threads) = threads {
+                    argv.extend(["--threads".to_string(), threads.to_string()]);
+                }
+                argv.extend(args.iter().cloned());
+                argv.extend(["--format".to_string(), "jsonl".to_string()]);
+                // `echo $$` before `exec` reports the search's own pid, so it can be stopped remotely
+                let script = format!("echo $$; exec {}", shell_join(&argv));
+                (host, format!("sh -c {}", shell_quote(&script)))
+            })
+            .collect()
+    }
+
+    /// Commands that copy the binary to `host` (after checking that it is not there yet)
+    fn upload_commands(&self, host: &SshHost) -> Option<Vec<Vec<String>>> {
+        let RemoteBinary::Upload { local, remote } = &self.binary else { return None };
+        let partial = format!("{}.partial", remote);
+        Some(vec![
+            ssh_argv(&host.destination, &format!("mkdir -p {}", shell_quote(INSTALL_DIR))),
+            vec![
+                "scp".to_string(),
+                "-p".to_string(),
+                "-q".to_string(),
+                "-o".to_string(),
+                "BatchMode=yes".to_string(),
+                local.display().to_string(),
+                format!("{}:{}", host.destination, partial),
+            ],
+            // Renamed into place so a failed copy is never mistaken for the binary
+            ssh_argv(&host.destination, &format!("mv {} {}", shell_quote(&partial), shell_quote(remote))),
+        ])
+    }
+
+    /// Make sure `host` can run the binary, uploading it if needed
+    fn prepare(&self, host: &SshHost) -> Result<(), VanityError> {
+        let program = self.binary.program();
+        let check = match self.binary {
+            RemoteBinary::Installed(_) => format!("command -v {}", shell_quote(program)),
+            RemoteBinary::Upload { .. } => format!("test -x {}", shell_quote(program)),
+        };
+        if ssh_status(&host.destination, &check)? {
+            return Ok(());
+        }
+        let Some(upload) = self.upload_commands(host) else {
+            return Err(VanityError::InvalidInput(format!(
+                "{} has no {} (install it or upload this binary)",
+                host.destination, program
+            )));
+        };
+        for argv in upload {
+            run(&argv)?;
+        }
+        Ok(())
+    }
+
+    fn job(&self, job: &JobHandle) -> Result<&Arc<SshJob>, VanityError> {
+        self.jobs.get(&job.id).ok_or_else(|| VanityError::InvalidInput(format!("no SSH job {}", job.id)))
+    }
+}
+
+/// Take `--threads N` out of the search arguments
+fn split_args(args: &[String]) -> (Option<usize>, Vec<String>) {
+    let mut threads = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => threads = args.next().and_then(|n| n.parse().ok()),
+            // Sessions always report in JSON lines
+            "--format" => {
+                args.next();
+            }
+            _ => rest.push(arg.clone()),
+        }
+    }
+    (threads, rest)
+}
+
+/// Threads per host: explicit counts stay, the others split `total` as evenly as possible (at least one
+/// each); without a total they use all their cores
+fn partition(total: Option<usize>, hosts: &[SshHost]) -> Vec<Option<usize>> {
+    let sharing = hosts.iter().filter(|h| h.threads.is_none()).count().max(1);
+    let mut index = 0;
+    hosts
+        .iter()
+        .map(|host| {
+            host.threads.or_else(|| {
+                let share = total.map(|total| (total / sharing + usize::from(index < total % sharing)).max(1));
+                index += 1;
+                share
+            })
+        })
+        .collect()
+}
+
+fn ssh_argv(destination: &str, remote: &str) -> Vec<String> {
+    ["ssh", "-o", "BatchMode=yes", destination, "--", remote].map(String::from).to_vec()
+}
+
+fn command(argv: &[String]) -> Command {
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]).stdin(Stdio::null());
+    command
+}
+
+/// Run a short remote command; `Ok(false)` when it ran and failed
+fn ssh_status(destination: &str, remote: &str) -> Result<bool, VanityError> {
+    let output = command(&ssh_argv(destination, remote)).output().map_err(|e| VanityError::io("cannot run ssh", e))?;
+    match output.status.code() {
+        // ssh itself failed (connection, authentication)
+        Some(255) | None => Err(VanityError::io(
+            format!("cannot reach {}", destination),
+            std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        )),
+        Some(code) => Ok(code == 0),
+    }
+}
+
+fn run(argv: &[String]) -> Result<(), VanityError> {
+    let output = command(argv).output().map_err(|e| VanityError::io(format!("cannot run {}", argv[0]), e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(VanityError::io(format!("{} failed", shell_join(argv)), std::io::Error::other(stderr)))
+    }
+}
+
+impl SshJob {
+    /// Stop the remote searches of every session but `keep`
+    fn stop(&self, keep: Option<usize>) {
+        for (i, session) in self.sessions.iter().enumerate() {
+            if Some(i) == keep {
+                continue;
+            }
+            let mut child = session.child.lock().unwrap();
+            if !matches!(child.try_wait(), Ok(None)) {
+                continue;
+            }
+            let pid = session.state.lock().unwrap().pid;
+            // SIGTERM lets the search print its summary; ending the session alone would leave it running
+            let stopped = pid.is_some_and(|pid| {
+                ssh_status(&session.destination, &format!("kill -TERM {}", pid)).unwrap_or(false)
+            });
+            if !stopped {
+                let _ = child.kill();
+            }
+        }
+    }
+
+    /// Follow one session's stdout: its pid, then JSON lines
+    fn read_stdout(self: Arc<Self>, index: usize, stdout: impl Read) {
+        let session = &self.sessions[index];
+        // Only the first stdout line can be the pid; stderr (host-key warnings, a MOTD) may have come first
+        let mut first = true;
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            let mut state = session.state.lock().unwrap();
+            if std::mem::take(&mut first) {
+                if let Ok(pid) = line.trim().parse() {
+                    state.pid = Some(pid);
+                    continue;
+                }
+            }
+            let event: Value = serde_json::from_str(&line).unwrap_or(Value::Null);
+            match event["event"].as_str() {
+                Some("progress") => {
+                    state.progress = Some((event["attempts"].as_u64().unwrap_or(0), event["rate"].as_f64().unwrap_or(0.0)));
+                }
+                Some("result") => {
+                    state.output.push(line);
+                    drop(state);
+                    self.stop(Some(index));
+                }
+                _ => state.output.push(line),
+            }
+        }
+    }
+
+    /// Collect one session's stderr with its other output
+    fn read_stderr(&self, index: usize, stderr: impl Read) {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            self.sessions[index].state.lock().unwrap().output.push(line);
+        }
+    }
+}
+
+impl Executor for SshExecutor {
+    fn name(&self) -> String {
+        "ssh".to_string()
+    }
+
+    /// The uploads (which `submit` skips on hosts that already have this build), then one session per host
+    fn plan(&self, job: &JobSpec) -> Vec<Vec<String>> {
+        let mut plan: Vec<Vec<String>> = self.hosts.iter().flat_map(|host| self.upload_commands(host)).flatten().collect();
+        plan.extend(self.host_commands(job).into_iter().map(|(host, command)| ssh_argv(&host.destination, &command)));
+        plan
+    }
+
+    fn submit(&mut self, job: &JobSpec) -> Result<JobHandle, VanityError> {
+        if self.hosts.is_empty() {
+            return Err(VanityError::InvalidInput("the SSH executor needs at least one host".to_string()));
+        }
+        for host in &self.hosts {
+            self.prepare(host)?;
+        }
+        let mut sessions = Vec::new();
+        let mut pipes = Vec::new();
+        for (host, session) in self.host_commands(job) {
+            let mut child = command(&ssh_argv(&host.destination, &session))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| VanityError::io("cannot run ssh", e))?;
+            pipes.push((child.stdout.take(), child.stderr.take()));
+            sessions.push(Session {
+                destination: host.destination.clone(),
+                child: Mutex::new(child),
+                state: Mutex::new(SessionState::default()),
+            });
+        }
+        let ssh_job = Arc::new(SshJob { sessions, cancelled: AtomicBool::new(false) });
+        for (index, (stdout, stderr)) in pipes.into_iter().enumerate() {
+            if let Some(stdout) = stdout {
+                let ssh_job = Arc::clone(&ssh_job);
+                thread::spawn(move || ssh_job.read_stdout(index, stdout));
+            }
+            if let Some(stderr) = stderr {
+                let ssh_job = Arc::clone(&ssh_job);
+                thread::spawn(move || ssh_job.read_stderr(index, stderr));
+            }
+        }
+        self.submitted += 1;
+        let id = format!("{}-{}", job.name, self.submitted);
+        self.jobs.insert(id.clone(), ssh_job);
+        Ok(JobHandle { executor: self.name(), id })
+    }
+
+    /// Running until every session has ended: succeeded if a host found a match
+    fn status(&mut self, job: &JobHandle) -> Result<JobStatus, VanityError> {
+        let ssh_job = self.job(job)?;
+        let mut failures = Vec::new();
+        let mut succeeded = false;
+        for session in &ssh_job.sessions {
+            let exit = session
+                .child
+                .lock()
+                .unwrap()
+                .try_wait()
+                .map_err(|e| VanityError::io(format!("cannot check the session on {}", session.destination), e))?;
+            match exit {
+                None => return Ok(JobStatus::Running),
+                Some(status) if status.success() => succeeded = true,
+                Some(status) => failures.push(format!("{}: {}", session.destination, status)),
+            }
+        }
+        Ok(if succeeded {
+            JobStatus::Succeeded
+        } else if ssh_job.cancelled.load(Ordering::SeqCst) {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Failed { reason: failures.join(", ") }
+        })
+    }
+
+    /// Each host's output, prefixed with its destination
+    fn logs(&mut self, job: &JobHandle) -> Result<String, VanityError> {
+        let mut logs = String::new();
+        for session in &self.job(job)?.sessions {
+            for line in &session.state.lock().unwrap().output {
+                logs.push_str(&format!("[{}] {}\n", session.destination, line));
+            }
+        }
+        Ok(logs)
+    }
+
+    fn cancel(&mut self, job: &JobHandle) -> Result<(), VanityError> {
+        let ssh_job = self.job(job)?;
+        ssh_job.cancelled.store(true, Ordering::SeqCst);
+        ssh_job.stop(None);
+        Ok(())
+    }
+
+    fn progress(&mut self, job: &JobHandle) -> Result<Option<JobProgress>, VanityError> {
+        let reports: Vec<(u64, f64)> =
+            self.job(job)?.sessions.iter().filter_map(|s| s.state.lock().unwrap().progress).collect();
+        Ok((!reports.is_empty()).then(|| JobProgress {
+            attempts: reports.iter().map(|r| r.0).sum(),
+            rate: reports.iter().map(|r| r.1).sum(),
+            reporting: reports.len(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(specs: &[&str]) -> Vec<SshHost> {
+        specs.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_hosts() {
+        assert_eq!("gpu-box".parse(), Ok(SshHost { destination: "gpu-box".to_string(), threads: None }));
+        assert_eq!("me@10.0.0.5=16".parse(), Ok(SshHost { destination: "me@10.0.0.5".to_string(), threads: Some(16) }));
+        for bad in ["", "=4", "box=0", "box=x", "-oProxyCommand=x", "a b"] {
+            assert!(bad.parse::<SshHost>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn threads_are_split_between_hosts() {
+        let three = hosts(&["a", "b=16", "c"]);
+        assert_eq!(partition(Some(5), &three), [Some(3), Some(16), Some(2)]);
+        assert_eq!(partition(Some(1), &three), [Some(1), Some(16), Some(1)]);
+        assert_eq!(partition(None, &three), [None, Some(16), None]);
+    }
+
+    #[test]
+    fn plan_quotes_each_host_command() {
+        let executor = SshExecutor::new(hosts(&["a", "b=2"]), RemoteBinary::Installed("solana-vanity-seed".to_string()));
+        let args = ["--threads", "8", "--regex", "^a'b", "--format", "json"].map(String::from).to_vec();
+        let plan = executor.plan(&JobSpec { name: "vanity".to_string(), args });
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[0][..5], ["ssh", "-o", "BatchMode=yes", "a", "--"]);
+        assert_eq!(
+            plan[1][5],
+            r#"sh -c 'echo $$; exec solana-vanity-seed --threads 2 --regex '\''^a'\''\'\'''\''b'\'' --format jsonl'"#
+        );
+    }
+
+    #[test]
+    fn pid_is_recorded_after_early_stderr() {
+        let child = Command::new("true").spawn().unwrap();
+        let session = Session {
+            destination: "a".to_string(),
+            child: Mutex::new(child),
+            state: Mutex::new(SessionState::default()),
+        };
+        let job = Arc::new(SshJob { sessions: vec![session], cancelled: AtomicBool::new(false) });
+        job.read_stderr(0, &b"Warning: Permanently added 'a' (ED25519) to the list of known hosts.\n"[..]);
+        Arc::clone(&job).read_stdout(0, &b"4242\n{\"event\":\"progress\",\"attempts\":10,\"rate\":5.0}\n"[..]);
+        let state = job.sessions[0].state.lock().unwrap();
+        assert_eq!(state.pid, Some(4242));
+        assert_eq!(state.progress, Some((10, 5.0)));
+        assert_eq!(state.output.len(), 1);
+        drop(state);
+        job.sessions[0].child.lock().unwrap().wait().unwrap();
+    }
+}
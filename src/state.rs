@@ -0,0 +1,143 @@
+use crate::error::VanityError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Search parameters recorded in a state file; a resume must use the same values
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// Description of a custom matcher, if any
+    #[serde(default)]
+    pub matcher: Option<String>,
+    pub generation: String,
+    pub words: usize,
+    pub threads: usize,
+}
+
+impl SearchConfig {
+    /// Whether a state file written with `other` describes the same search (thread count may differ)
+    pub fn same_search(&self, other: &SearchConfig) -> bool {
+        self.prefix == other.prefix
+            && self.suffix == other.suffix
+            && self.regex == other.regex
+            && self.matcher == other.matcher
+            && self.generation == other.generation
+            && self.words == other.words
+    }
+}
+
+/// A matching key recorded in the state file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoundKey {
+    pub public_address: String,
+    pub private_key: Option<String>,
+    pub mnemonic: Option<String>,
+}
+
+/// Progress snapshot persisted to a state file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchState {
+    pub config: SearchConfig,
+    pub attempts: u64,
+    pub elapsed_secs: f64,
+    pub results: Vec<FoundKey>,
+}
+
+impl SearchState {
+    pub fn new(config: SearchConfig) -> Self {
+        SearchState { config, attempts: 0, elapsed_secs: 0.0, results: Vec::new() }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data = fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the state atomically so a preempted write never corrupts it
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_secret_json(path, self)
+    }
+}
+
+/// Write `value` as JSON atomically (temp file + rename), readable by the owner only since it may
+/// contain private keys
+pub(crate) fn save_secret_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let data = serde_json::to_string_pretty(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut file = opts.open(&tmp)?;
+    file.write_all(data.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+/// Where and how often a search records its progress
+#[derive(Debug)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    pub interval: Duration,
+    pub state: SearchState,
+}
+
+impl Checkpoint {
+    /// Open the state file for `config`. With `resume`, an existing file is loaded and must describe
+    /// the same search; a missing one starts a fresh state. Without `resume` an existing file is an
+    /// error, since it may hold a found key. The state is written once up front so an unwritable path
+    /// fails now rather than at the first checkpoint.
+    pub fn open(path: PathBuf, interval: Duration, resume: bool, config: SearchConfig) -> Result<Self, VanityError> {
+        let state = if resume && path.exists() {
+            let state = SearchState::load(&path).map_err(|e| {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    VanityError::InvalidInput(format!("state file {} is not valid: {}", path.display(), e))
+                } else {
+                    VanityError::io(format!("cannot read state file {}", path.display()), e)
+                }
+            })?;
+            if !state.config.same_search(&config) {
+                return Err(VanityError::InvalidInput(format!(
+                    "state file {} was recorded for a different search: {:?}",
+                    path.display(),
+                    state.config
+                )));
+            }
+            // A completed search is returned as-is so the caller can report its result
+            if !state.results.is_empty() {
+                return Ok(Checkpoint { path, interval, state });
+            }
+            SearchState { config, ..state }
+        } else if path.exists() {
+            return Err(VanityError::InvalidInput(format!(
+                "state file {} exists; pass --resume or choose another path",
+                path.display()
+            )));
+        } else {
+            SearchState::new(config)
+        };
+        state
+            .save(&path)
+            .map_err(|e| VanityError::io(format!("cannot write state file {}", path.display()), e))?;
+        Ok(Checkpoint { path, interval, state })
+    }
+
+    /// Record the current totals (and any new result) to the state file
+    pub fn save(&mut self, attempts: u64, elapsed_secs: f64, found: Option<FoundKey>) -> io::Result<()> {
+        self.state.attempts = attempts;
+        self.state.elapsed_secs = elapsed_secs;
+        self.state.results.extend(found);
+        self.state.save(&self.path)
+    }
+}
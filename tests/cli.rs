@@ -0,0 +1,472 @@
+//! Runs the binary end to end on 1-character patterns, which match within a few hundred candidates.
+
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(args)
+        .env_remove("VANITY_PASSPHRASE")
+        .output()
+        .expect("binary runs")
+}
+
+/// Run a search with `--format json` and return the result record
+fn search_json(args: &[&str]) -> Value {
+    let mut all = vec!["--threads", "2", "--format", "json"];
+    all.extend_from_slice(args);
+    let out = run(&all);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let result: Value = serde_json::from_slice(&out.stdout).expect("stdout is one JSON document");
+    assert_eq!(result["event"], "result");
+    result
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vanity-cli-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn raw_suffix_search_finds_matching_address() {
+    let result = search_json(&["--raw", "--suffix", "a"]);
+    let address = result["public_address"].as_str().unwrap();
+    assert!(address.ends_with('a'));
+    // Lowercase pattern: the preceding character must be uppercase
+    assert!(address.chars().rev().nth(1).unwrap().is_ascii_uppercase(), "{}", address);
+    let private_key = bs58::decode(result["private_key"].as_str().unwrap()).into_vec().unwrap();
+    assert_eq!(private_key.len(), 64);
+    assert!(result["mnemonic"].is_null());
+}
+
+#[test]
+fn raw_prefix_search_finds_matching_address() {
+    let result = search_json(&["--raw", "--prefix", "B"]);
+    let address = result["public_address"].as_str().unwrap();
+    assert!(address.starts_with('B'));
+    assert!(address.chars().nth(1).unwrap().is_ascii_lowercase(), "{}", address);
+}
+
+#[test]
+fn mnemonic_search_reports_phrase() {
+    let result = search_json(&["--suffix", "z"]);
+    assert_eq!(result["generation"], "mnemonic");
+    assert_eq!(result["mnemonic"].as_str().unwrap().split(' ').count(), 12);
+    assert!(result["public_address"].as_str().unwrap().ends_with('z'));
+}
+
+#[test]
+fn token_search_omits_private_key() {
+    let result = search_json(&["--token", "--suffix", "a"]);
+    assert!(result["private_key"].is_null());
+    assert_eq!(result["generation"], "token");
+}
+
+#[test]
+fn text_output_goes_to_stdout_and_progress_to_stderr() {
+    let out = run(&["--threads", "2", "--raw", "--suffix", "a"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Public Address: "), "{}", stdout);
+    assert!(stdout.contains("Base58 Private Key: "), "{}", stdout);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Starting search"));
+}
+
+#[test]
+fn outfile_stores_keypair_instead_of_printing_it() {
+    let path = temp_path("keypair.json");
+    let result = search_json(&["--raw", "--suffix", "a", "--outfile", path.to_str().unwrap()]);
+    assert!(result["private_key"].is_null());
+    assert_eq!(result["stored_at"][0], path.to_str().unwrap());
+    let bytes: Vec<u8> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(bytes.len(), 64);
+    // Existing key files are never overwritten
+    let out = run(&["--raw", "--suffix", "a", "--outfile", path.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Base58 Private Key: "));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn seeded_search_is_reproducible() {
+    // Golden value: changing it means seeded key generation changed
+    let golden = "E3VsVRQ85HmfMTBbdujU5efsfU4DsUPiSaeQ9AHgq8Ea";
+    for threads in ["1", "3"] {
+        let out = run(&["--rng-seed", "7", "--threads", threads, "--raw", "--suffix", "a", "--format", "json"]);
+        assert!(out.status.success());
+        let result: Value = serde_json::from_slice(&out.stdout).unwrap();
+        assert_eq!(result["public_address"], golden, "threads={}", threads);
+    }
+}
+
+#[test]
+fn invalid_pattern_exits_with_code_2() {
+    let out = run(&["--prefix", "0OIl"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Invalid character"));
+}
+
+#[test]
+fn missing_pattern_exits_with_code_2() {
+    assert_eq!(run(&["--raw"]).status.code(), Some(2));
+}
+
+#[test]
+fn exhausted_budget_exits_with_code_3() {
+    let out = run(&["--threads", "1", "--raw", "--prefix", "Zzzzzz", "--max-attempts", "10", "--format", "json"]);
+    assert_eq!(out.status.code(), Some(3));
+    let stopped: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(stopped["event"], "stopped");
+    assert_eq!(stopped["attempts"], 10);
+}
+
+#[test]
+fn existing_state_file_needs_resume() {
+    let path = temp_path("state.json");
+    std::fs::write(&path, "{}").unwrap();
+    let out = run(&["--threads", "1", "--raw", "--suffix", "a", "--state-file", path.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("pass --resume"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn completed_state_file_reports_its_result() {
+    let path = temp_path("completed.json");
+    let state = path.to_str().unwrap();
+    let found = search_json(&["--raw", "--suffix", "a", "--state-file", state]);
+    let again = search_json(&["--raw", "--suffix", "a", "--state-file", state, "--resume"]);
+    assert_eq!(again["public_address"], found["public_address"]);
+    assert_eq!(again["private_key"], found["private_key"]);
+    assert_eq!(again["attempts"], found["attempts"]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn show_alphabet_prints_base58() {
+    let out = run(&["--show-alphabet"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout)
+        .contains("123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"));
+}
+
+#[test]
+fn dry_run_prints_submission_without_running_it() {
+    let out = run(&["--raw", "--suffix", "a", "--threads", "4", "--executor", "cpu", "--dry-run"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("aws batch submit-job --job-name vanity-search-cpu --job-queue cpu-queue"), "{}", stdout);
+    assert!(stdout.contains(r#"'{"command":["--threads","4","--raw","--suffix","a"]}'"#), "{}", stdout);
+}
+
+#[test]
+fn remote_jobs_refuse_local_only_options() {
+    for extra in [&["--outfile", "key.json"][..], &["--sink", "jsonl:hits.jsonl"], &["--state-file", "s.json"], &["--rng-seed", "7"]] {
+        let mut args = vec!["--raw", "--suffix", "a", "--executor", "cpu", "--dry-run"];
+        args.extend(extra);
+        let out = run(&args);
+        assert_eq!(out.status.code(), Some(2), "{:?}", extra);
+        assert!(String::from_utf8_lossy(&out.stderr).contains(extra[0]), "{:?}", extra);
+    }
+}
+
+#[test]
+fn profiles_come_from_the_config_file() {
+    let config = temp_path("profiles.toml");
+    std::fs::write(
+        &config,
+        "[profiles.spot]\ntype = \"aws-batch\"\njob = \"nightly\"\nqueue = \"spot\"\nthreads = 32\nlimits = { cpus = 32 }\n",
+    )
+    .unwrap();
+    let config_arg = config.to_str().unwrap();
+    let out = run(&["--raw", "--suffix", "a", "--config", config_arg, "--profile", "spot", "--dry-run"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("aws batch submit-job --job-name nightly --job-queue spot"), "{}", stdout);
+    assert!(stdout.contains(r#""command":["--threads","32","--raw","--suffix","a"]"#), "{}", stdout);
+    assert!(stdout.contains(r#""resourceRequirements":[{"type":"VCPU","value":"32"}]"#), "{}", stdout);
+    let out = run(&["--raw", "--suffix", "a", "--config", config_arg, "--profile", "gpu", "--dry-run"]);
+    assert_eq!(out.status.code(), Some(2));
+    std::fs::remove_file(&config).unwrap();
+}
+
+#[test]
+fn render_job_emits_a_fanned_out_manifest() {
+    let out = run(&[
+        "--raw", "--suffix", "a", "--threads", "8", "render-job", "--target", "kubernetes", "--image", "vanity:1",
+        "--parallelism", "4",
+    ]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let manifest: Value = serde_json::from_slice(&out.stdout).expect("stdout is one JSON document");
+    assert_eq!(manifest["kind"], "Job");
+    assert_eq!(manifest["spec"]["parallelism"], 4);
+    assert_eq!(manifest["spec"]["completions"], 4);
+    let container = &manifest["spec"]["template"]["spec"]["containers"][0];
+    assert_eq!(container["image"], "vanity:1");
+    assert_eq!(container["args"], serde_json::json!(["--threads", "8", "--raw", "--suffix", "a"]));
+    // Without an image there is nothing to run
+    let out = run(&["--raw", "--suffix", "a", "render-job", "--target", "cloud-run"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn process_executor_follows_the_job_to_its_result() {
+    let dir = temp_path("jobs");
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["--threads", "1", "--raw", "--suffix", "a", "--executor", "process", "--progress-interval", "1"])
+        .current_dir(&dir)
+        .output()
+        .expect("binary runs");
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Submitted job") && stderr.contains("succeeded"), "{}", stderr);
+    // The job's log, echoed on stdout, carries the result
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Public Address: "), "{}", stdout);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Stand-ins for `ssh` and `scp` that run everything on this machine, in the working directory
+/// (playing the remote home). The search on host `stuck` never finds anything.
+#[cfg(unix)]
+const FAKE_SSH: &str = r#"#!/bin/sh
+while [ "$1" = "-o" ]; do shift 2; done
+dest=$1; shift 2
+case "$dest:$1" in
+  stuck:*jsonl*) exec sh -c 'echo $$; exec sleep 600' ;;
+esac
+exec sh -c "$1"
+"#;
+#[cfg(unix)]
+const FAKE_SCP: &str = r#"#!/bin/sh
+for arg; do src=$dst; dst=$arg; done
+exec cp -p "$src" "${dst#*:}"
+"#;
+
+#[cfg(unix)]
+#[test]
+fn ssh_executor_stops_every_host_after_a_match() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    let dir = temp_path("ssh");
+    let bin = dir.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    for (name, script) in [("ssh", FAKE_SSH), ("scp", FAKE_SCP)] {
+        std::fs::write(bin.join(name), script).unwrap();
+        std::fs::set_permissions(bin.join(name), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["--raw", "--suffix", "a", "--executor", "ssh", "--ssh-host", "fast=1", "--ssh-host", "stuck"])
+        .args(["--ssh-upload", "--progress-interval", "1"])
+        .env("PATH", path)
+        .current_dir(&dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("binary runs");
+    // `stuck` only ends if the executor kills its search
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while child.try_wait().unwrap().is_none() {
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("the SSH job did not stop after the match");
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains(r#"[fast] {"event":"result""#), "{}", stdout);
+    // The binary was uploaded once, under its content hash
+    let installed = std::fs::read_dir(dir.join(".cache/solana-vanity-seed")).unwrap().count();
+    assert_eq!(installed, 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Stand-in for `docker` that runs the image's search on this machine, with the volume's host directory
+/// in place of the container's mount point; state lives in the working directory
+#[cfg(unix)]
+const FAKE_DOCKER: &str = r#"#!/bin/sh
+cmd=$1; shift
+case "$cmd" in
+  build) echo sha256:0 ;;
+  run)
+    while [ $# -gt 0 ]; do
+      case "$1" in
+        --detach) shift ;;
+        --name) name=$2; shift 2 ;;
+        --volume) volume=${2%:*}; mount=${2##*:}; shift 2 ;;
+        --*) shift 2 ;;
+        *) shift; break ;;
+      esac
+    done
+    for arg; do
+      shift
+      case "$arg" in jsonl:$mount/*) arg="jsonl:$volume/${arg#jsonl:$mount/}" ;; esac
+      set -- "$@" "$arg"
+    done
+    ( "$FAKE_DOCKER_SEARCH" "$@" > "$name.out" 2> "$name.err"; echo $? > "$name.exit" ) > /dev/null 2>&1 &
+    echo "$name" ;;
+  inspect)
+    for name; do :; done
+    if [ -f "$name.exit" ]; then
+      echo "{\"Status\":\"exited\",\"ExitCode\":$(cat "$name.exit")}"
+    else
+      echo '{"Status":"running","ExitCode":0}'
+    fi ;;
+  logs) cat "$1.out" ;;
+  *) exit 1 ;;
+esac
+"#;
+
+#[cfg(unix)]
+#[test]
+fn docker_executor_collects_the_result_from_the_volume() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_path("docker");
+    let bin = dir.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::write(bin.join("docker"), FAKE_DOCKER).unwrap();
+    std::fs::set_permissions(bin.join("docker"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    let out = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["--raw", "--suffix", "a", "--threads", "1", "--executor", "docker", "--container-cpus", "1"])
+        .args(["--progress-interval", "1"])
+        .env("PATH", path)
+        .env("FAKE_DOCKER_SEARCH", env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .current_dir(&dir)
+        .output()
+        .expect("binary runs");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "stderr: {}", stderr);
+    assert!(stderr.contains("Result collected in"), "{}", stderr);
+    let jobs: Vec<_> = std::fs::read_dir(dir.join("vanity-results")).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(std::fs::metadata(&jobs[0]).unwrap().permissions().mode() & 0o777, 0o700);
+    let stored = std::fs::read_to_string(jobs[0].join("result.jsonl")).unwrap();
+    let result: Value = serde_json::from_str(stored.trim()).expect("one JSON line");
+    assert!(result["public_address"].as_str().unwrap().ends_with('a'));
+    assert!(result["private_key"].is_string());
+    // The terminal output inside the container leaves the secret to the result file
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains(result["public_address"].as_str().unwrap()), "{}", stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[ignore = "needs an sshd on localhost that accepts this user's key without a prompt"]
+fn ssh_executor_searches_on_localhost() {
+    let out = run(&[
+        "--raw", "--suffix", "a", "--executor", "ssh", "--ssh-host", "localhost=1", "--ssh-host", "127.0.0.1=1",
+        "--ssh-upload",
+    ]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains(r#"{"event":"result""#));
+}
+
+/// Send one HTTP request and return the status code and JSON body
+fn http(addr: &str, request: &str) -> (u16, Value) {
+    use std::io::{Read, Write};
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").expect("complete response");
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn serve_runs_jobs_submitted_over_http() {
+    use std::io::{BufRead, BufReader};
+    use std::time::{Duration, Instant};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["--threads", "1", "serve", "--listen", "127.0.0.1:0"])
+        .env("VANITY_API_TOKEN", "s3cret")
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("binary runs");
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap()).read_line(&mut banner).unwrap();
+    let addr = banner.split("http://").nth(1).and_then(|rest| rest.split_whitespace().next()).unwrap().to_string();
+    let post = |auth: &str| {
+        let body = r#"{"suffix": "a", "generation": "raw"}"#;
+        format!("POST /jobs HTTP/1.1\r\nHost: {}\r\n{}Content-Length: {}\r\n\r\n{}", addr, auth, body.len(), body)
+    };
+    assert_eq!(http(&addr, &post("")).0, 401);
+    let (status, job) = http(&addr, &post("Authorization: Bearer s3cret\r\n"));
+    assert_eq!(status, 201, "{}", job);
+    let get = |path: &str| http(&addr, &format!("GET {} HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer s3cret\r\n\r\n", path, addr));
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while get(&format!("/jobs/{}", job["id"])).1["status"] == "running" {
+        assert!(Instant::now() < deadline, "the job did not finish");
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let (status, result) = get(&format!("/jobs/{}/result", job["id"]));
+    assert_eq!(status, 200, "{}", result);
+    assert!(result["public_address"].as_str().unwrap().ends_with('a'));
+    assert!(result["private_key"].is_string());
+    assert_eq!(get(&format!("/jobs/{}/result", job["id"])).0, 410);
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn serve_needs_a_token_beyond_loopback() {
+    let out = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["serve", "--listen", "0.0.0.0:0"])
+        .env_remove("VANITY_API_TOKEN")
+        .output()
+        .expect("binary runs");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("VANITY_API_TOKEN"));
+    let out = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["serve", "--max-jobs", "0"])
+        .output()
+        .expect("binary runs");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--max-jobs"));
+}
+
+#[test]
+fn serve_refuses_browser_and_foreign_host_requests() {
+    use std::io::{BufRead, BufReader};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solana-vanity-seed"))
+        .args(["serve", "--listen", "127.0.0.1:0"])
+        .env_remove("VANITY_API_TOKEN")
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("binary runs");
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap()).read_line(&mut banner).unwrap();
+    let addr = banner.split("http://").nth(1).and_then(|rest| rest.split_whitespace().next()).unwrap().to_string();
+    let port = addr.rsplit(':').next().unwrap();
+    let get = |headers: &str| http(&addr, &format!("GET /jobs HTTP/1.1\r\n{}\r\n", headers));
+    assert_eq!(get(&format!("Host: {}\r\n", addr)).0, 200);
+    assert_eq!(get(&format!("Host: localhost:{}\r\n", port)).0, 200);
+    // DNS rebinding: the browser sends the attacker's name as Host
+    assert_eq!(get(&format!("Host: rebind.example:{}\r\n", port)).0, 403);
+    assert_eq!(get("").0, 403);
+    assert_eq!(get(&format!("Host: {}\r\nOrigin: http://rebind.example\r\n", addr)).0, 403);
+    let body = r#"{"suffix": "a", "generation": "raw"}"#;
+    let post = format!(
+        "POST /jobs HTTP/1.1\r\nHost: localhost:{}\r\nOrigin: null\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+        port,
+        body.len(),
+        body
+    );
+    assert_eq!(http(&addr, &post).0, 403);
+    assert_eq!(get(&format!("Host: {}\r\n", addr)).1["jobs"], serde_json::json!([]));
+    child.kill().unwrap();
+    child.wait().unwrap();
+}